@@ -0,0 +1,145 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::future::Future;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use sys::System;
+use ::SystemExt;
+
+#[derive(Default)]
+struct Shared {
+    result: Option<thread::Result<()>>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by the `System::refresh_*_async` methods. It resolves once the refresh, run
+/// on a separate thread, is done. If the refresh panicked, it resolves to an `Err` containing
+/// the panic payload, like [`JoinHandle::join`] does.
+///
+/// [`JoinHandle::join`]: https://doc.rust-lang.org/std/thread/struct.JoinHandle.html#method.join
+pub struct RefreshFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Future for RefreshFuture {
+    type Output = thread::Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<thread::Result<()>> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl System {
+    /// Runs `f` on `system` from a new thread so the blocking `/proc` reads don't block the
+    /// async executor. Each call gets its own thread, so refreshing a `System` doesn't delay the
+    /// refreshes of the other ones. `system` is locked while `f` runs.
+    ///
+    /// Returns an error if the thread couldn't be spawned.
+    ///
+    /// ```ignore
+    /// let system = Arc::new(Mutex::new(System::new()));
+    /// System::refresh_async(&system, |s| s.refresh_disks())?.await.expect("refresh panicked");
+    /// ```
+    pub fn refresh_async<F>(system: &Arc<Mutex<System>>, f: F) -> io::Result<RefreshFuture>
+        where F: FnOnce(&mut System) + Send + 'static {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let thread_shared = shared.clone();
+        let system = system.clone();
+
+        thread::Builder::new().name("sysinfo-refresh".to_owned()).spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                // A previous refresh which panicked doesn't make the system unusable.
+                let mut system = system.lock().unwrap_or_else(|e| e.into_inner());
+                f(&mut system);
+            }));
+            let mut shared = thread_shared.lock().unwrap();
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        })?;
+        Ok(RefreshFuture { shared: shared })
+    }
+
+    /// Async version of [`refresh_all`](trait.SystemExt.html#method.refresh_all).
+    pub fn refresh_all_async(system: &Arc<Mutex<System>>) -> io::Result<RefreshFuture> {
+        System::refresh_async(system, |s| s.refresh_all())
+    }
+
+    /// Async version of [`refresh_system`](trait.SystemExt.html#tymethod.refresh_system).
+    pub fn refresh_system_async(system: &Arc<Mutex<System>>) -> io::Result<RefreshFuture> {
+        System::refresh_async(system, |s| s.refresh_system())
+    }
+
+    /// Async version of [`refresh_processes`](trait.SystemExt.html#tymethod.refresh_processes).
+    pub fn refresh_processes_async(system: &Arc<Mutex<System>>) -> io::Result<RefreshFuture> {
+        System::refresh_async(system, |s| s.refresh_processes())
+    }
+
+    /// Async version of [`refresh_disks`](trait.SystemExt.html#tymethod.refresh_disks).
+    pub fn refresh_disks_async(system: &Arc<Mutex<System>>) -> io::Result<RefreshFuture> {
+        System::refresh_async(system, |s| s.refresh_disks())
+    }
+}
+
+// Minimal executor: polls `future` on the current thread, parking it until it's woken up.
+#[cfg(test)]
+fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::Wake;
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn test_refresh_processes_async() {
+    let system = Arc::new(Mutex::new(System::new()));
+    let future = System::refresh_processes_async(&system).expect("failed to spawn");
+    assert!(block_on(future).is_ok());
+    let pid = ::utils::get_current_pid();
+    assert!(system.lock().unwrap().get_process(pid).is_some());
+}
+
+#[test]
+fn test_refresh_async_panic() {
+    let system = Arc::new(Mutex::new(System::new()));
+    let future = System::refresh_async(&system, |_| panic!("refresh failed"))
+        .expect("failed to spawn");
+    let payload = block_on(future).expect_err("the panic wasn't reported");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"refresh failed"));
+
+    // The system can still be refreshed.
+    let future = System::refresh_all_async(&system).expect("failed to spawn");
+    assert!(block_on(future).is_ok());
+}
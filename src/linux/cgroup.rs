@@ -0,0 +1,118 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::system::get_all_data;
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+// Returns the path of the cgroup `controller` belongs to in the content of a
+// `/proc/[pid]/cgroup` file. An empty `controller` matches the unified (v2) hierarchy.
+pub fn find_cgroup_path(data: &str, controller: &str) -> Option<String> {
+    for line in data.lines() {
+        // Lines look like "4:memory:/user.slice" (v1) or "0::/user.slice" (v2).
+        let mut parts = line.splitn(3, ':');
+        if let (Some(_), Some(controllers), Some(path)) = (parts.next(), parts.next(),
+                                                           parts.next()) {
+            if (controller.is_empty() && controllers.is_empty()) ||
+               (!controller.is_empty() && controllers.split(',').any(|c| c == controller)) {
+                return Some(path.to_owned());
+            }
+        }
+    }
+    None
+}
+
+// Returns the cgroup path of the unified (v2) hierarchy in the content of a `/proc/[pid]/cgroup`
+// file, or the first v1 one if the unified hierarchy isn't used.
+pub fn find_main_cgroup_path(data: &str) -> Option<String> {
+    find_cgroup_path(data, "").or_else(|| {
+        data.lines().next().and_then(|line| line.splitn(3, ':').nth(2)).map(|p| p.to_owned())
+    })
+}
+
+// Returns the directories where the files of the given v1 `controller` (or of the unified
+// hierarchy if `controller` is empty) can be found for the current process, the most specific
+// one first.
+pub fn get_cgroup_dirs(controller: &str) -> Vec<PathBuf> {
+    let mut ret = Vec::new();
+    let root = if controller.is_empty() {
+        PathBuf::from(CGROUP_ROOT)
+    } else {
+        Path::new(CGROUP_ROOT).join(controller)
+    };
+    let data = get_all_data("/proc/self/cgroup").unwrap_or_default();
+    if let Some(path) = find_cgroup_path(&data, controller) {
+        let path = path.trim_start_matches('/');
+        if !path.is_empty() {
+            ret.push(root.join(path));
+        }
+    }
+    // Inside a container, the cgroup namespace usually makes its own cgroup the root one.
+    ret.push(root);
+    ret
+}
+
+fn read_value<P: AsRef<Path>>(path: P) -> Option<String> {
+    get_all_data(path).ok().map(|s| s.trim().to_owned())
+}
+
+// Returns the CPU quota of the current process' cgroup, as a number of CPUs. `None` means
+// that there is no limit.
+pub fn get_cpu_quota() -> Option<f64> {
+    for dir in get_cgroup_dirs("") {
+        // cgroup v2: "max 100000" or "200000 100000".
+        if let Some(content) = read_value(dir.join("cpu.max")) {
+            let mut parts = content.split_whitespace();
+            return match (parts.next().map(f64::from_str), parts.next().map(f64::from_str)) {
+                (Some(Ok(quota)), Some(Ok(period))) if period > 0. => Some(quota / period),
+                _ => None,
+            };
+        }
+    }
+    for dir in get_cgroup_dirs("cpu") {
+        let quota = read_value(dir.join("cpu.cfs_quota_us")).and_then(|s| i64::from_str(&s).ok());
+        let period = read_value(dir.join("cpu.cfs_period_us")).and_then(|s| i64::from_str(&s).ok());
+        match (quota, period) {
+            (Some(quota), Some(period)) if quota > 0 && period > 0 => {
+                return Some(quota as f64 / period as f64);
+            }
+            (Some(_), Some(_)) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Returns the memory usage and limit (in bytes) of the current process' cgroup. `None` means
+// that there is no limit.
+pub fn get_memory_usage() -> Option<(u64, u64)> {
+    for dir in get_cgroup_dirs("") {
+        // cgroup v2: the limit is "max" when there is none.
+        if let Some(limit) = read_value(dir.join("memory.max")) {
+            let usage = read_value(dir.join("memory.current")).and_then(|s| u64::from_str(&s).ok());
+            return match (usage, u64::from_str(&limit)) {
+                (Some(usage), Ok(limit)) => Some((usage, limit)),
+                _ => None,
+            };
+        }
+    }
+    for dir in get_cgroup_dirs("memory") {
+        let usage = read_value(dir.join("memory.usage_in_bytes"))
+            .and_then(|s| u64::from_str(&s).ok());
+        let limit = read_value(dir.join("memory.limit_in_bytes"))
+            .and_then(|s| u64::from_str(&s).ok());
+        match (usage, limit) {
+            // Without limit, v1 reports the biggest page-aligned `i64` value.
+            (Some(_), Some(limit)) if limit >= i64::MAX as u64 & !0xfff => return None,
+            (Some(usage), Some(limit)) => return Some((usage, limit)),
+            _ => {}
+        }
+    }
+    None
+}
@@ -5,9 +5,13 @@
 //
 
 use std::collections::HashMap;
-use std::fs::{File, read_dir};
+use std::fs::{File, canonicalize, read_dir};
 use std::io::Read;
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::system::count_file_read;
 
 /// More information can be found at
 /// http://lxr.free-electrons.com/source/Documentation/hwmon/sysfs-interface
@@ -20,7 +24,15 @@ pub struct Component {
     pub critical: Option<f32>,
     /// Component's label.
     pub label: String,
+    // Last value read from the sensor, as written by the kernel.
+    temperature_millidegrees: i64,
     input_file: PathBuf,
+    // Kept opened to avoid opening the file again on each update.
+    input_handle: Option<File>,
+    chip_name: String,
+    device_path: PathBuf,
+    update_interval: Option<Duration>,
+    read_at: Option<Instant>,
 }
 
 fn get_file_line(file: &Path) -> Option<String> {
@@ -33,7 +45,7 @@ fn get_file_line(file: &Path) -> Option<String> {
     }
 }
 
-fn append_files(components: &mut Vec<Component>, folder: &Path) {
+fn append_files(components: &mut Vec<Component>, folder: &Path, label_prefix: Option<&str>) {
     let mut matchings = HashMap::new();
     if let Ok(dir) = read_dir(folder) {
         for entry in dir {
@@ -52,40 +64,104 @@ fn append_files(components: &mut Vec<Component>, folder: &Path) {
             }
         }
         for (key, val) in &matchings {
-            let mut found_input = None;
-            let mut found_label = None;
-            for (pos, v) in val.iter().enumerate() {
-                match v.as_str() {
-                    "input" => { found_input = Some(pos); }
-                    "label" => { found_label = Some(pos); }
-                    _ => {}
+            let has_input = val.iter().any(|v| v == "input");
+            let has_label = val.iter().any(|v| v == "label");
+            // Sensors without label are only kept when we know what they belong to.
+            if !has_input || (!has_label && label_prefix.is_none()) {
+                continue
+            }
+            let mut p_label = folder.to_path_buf();
+            let mut p_input = folder.to_path_buf();
+            let mut p_crit = folder.to_path_buf();
+            let mut p_max = folder.to_path_buf();
+
+            p_label.push(&format!("temp{}_label", key));
+            p_input.push(&format!("temp{}_input", key));
+            p_max.push(&format!("temp{}_max", key));
+            p_crit.push(&format!("temp{}_crit", key));
+            let label = if has_label {
+                get_file_line(p_label.as_path()).unwrap().replace("\n", "")
+            } else {
+                format!("temp{}", key)
+            };
+            let label = match label_prefix {
+                Some(prefix) => format!("{} {}", prefix, label),
+                None => label,
+            };
+            let max = if let Some(max) = get_file_line(p_max.as_path()) {
+                Some(max.replace("\n", "").parse::<f32>().unwrap() / 1000f32)
+            } else {
+                None
+            };
+            let crit = if let Some(crit) = get_file_line(p_crit.as_path()) {
+                Some(crit.replace("\n", "").parse::<f32>().unwrap() / 1000f32)
+            } else {
+                None
+            };
+            components.push(Component::new(label, p_input.as_path(), max, crit));
+        }
+    }
+}
+
+// GPUs expose their sensors through the hwmon folder of their DRM device. They're often
+// unlabelled so the card name is used to identify them.
+fn append_gpu_files(components: &mut Vec<Component>, gpu_folders: &mut Vec<PathBuf>) {
+    if let Ok(dir) = read_dir("/sys/class/drm/") {
+        for entry in dir.filter_map(|e| e.ok()) {
+            let card = entry.file_name().to_str().unwrap_or("").to_owned();
+            // Connectors are listed as "card0-DP-1", we only want the cards themselves.
+            if !card.starts_with("card") || card.contains('-') {
+                continue;
+            }
+            if let Ok(hwmons) = read_dir(entry.path().join("device/hwmon")) {
+                for hwmon in hwmons.filter_map(|e| e.ok()) {
+                    let folder = hwmon.path();
+                    append_files(components, &folder, Some(&format!("GPU {}", card)));
+                    if let Ok(folder) = canonicalize(&folder) {
+                        gpu_folders.push(folder);
+                    }
                 }
-                if found_label.is_some() && found_input.is_some() {
-                    let mut p_label = folder.to_path_buf();
-                    let mut p_input = folder.to_path_buf();
-                    let mut p_crit = folder.to_path_buf();
-                    let mut p_max = folder.to_path_buf();
-
-                    p_label.push(&format!("temp{}_label", key));
-                    p_input.push(&format!("temp{}_input", key));
-                    p_max.push(&format!("temp{}_max", key));
-                    p_crit.push(&format!("temp{}_crit", key));
-                    let content = get_file_line(p_label.as_path()).unwrap();
-                    let label = content.replace("\n", "");
-                    let max = if let Some(max) = get_file_line(p_max.as_path()) {
-                        Some(max.replace("\n", "").parse::<f32>().unwrap() / 1000f32)
-                    } else {
-                        None
-                    };
-                    let crit = if let Some(crit) = get_file_line(p_crit.as_path()) {
-                        Some(crit.replace("\n", "").parse::<f32>().unwrap() / 1000f32)
-                    } else {
-                        None
-                    };
-                    components.push(Component::new(label, p_input.as_path(), max, crit));
-                    break
+            }
+        }
+    }
+}
+
+// Thermal zones are mostly used on ARM boards, where hwmon isn't always available. A zone is
+// skipped if it's already registered as a hwmon chip with a labelled sensor.
+fn append_thermal_zones(components: &mut Vec<Component>) {
+    if let Ok(dir) = read_dir("/sys/class/thermal/") {
+        for entry in dir.filter_map(|e| e.ok()) {
+            let folder = entry.path();
+            if !entry.file_name().to_str().unwrap_or("").starts_with("thermal_zone") {
+                continue;
+            }
+            let zone_type = match get_file_line(&folder.join("type")) {
+                Some(zone_type) => zone_type.trim().to_owned(),
+                None => continue,
+            };
+            let p_input = folder.join("temp");
+            if components.iter().any(|c| c.chip_name == zone_type) ||
+               get_file_line(&p_input).is_none() {
+                continue;
+            }
+            // The critical temperature is one of the trip points.
+            let mut crit = None;
+            for i in 0.. {
+                let p_trip_type = folder.join(format!("trip_point_{}_type", i));
+                let trip_type = match get_file_line(&p_trip_type) {
+                    Some(trip_type) => trip_type,
+                    None => break,
+                };
+                if trip_type.trim() == "critical" {
+                    crit = get_file_line(&folder.join(format!("trip_point_{}_temp", i)))
+                        .and_then(|t| t.trim().parse::<f32>().ok())
+                        .map(|t| t / 1000f32);
+                    break;
                 }
             }
+            let mut component = Component::new(zone_type.clone(), &p_input, None, crit);
+            component.chip_name = zone_type;
+            components.push(component);
         }
     }
 }
@@ -94,10 +170,24 @@ impl Component {
     /// Creates a new component with the given information.
     pub fn new(label: String, input_path: &Path, max: Option<f32>,
                critical: Option<f32>) -> Component {
+        let device_path = input_path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let chip_name = get_file_line(&device_path.join("name"))
+            .map(|name| name.trim().to_owned())
+            .unwrap_or_default();
+        // Only hwmon chips provide it, in milliseconds.
+        let update_interval = get_file_line(&device_path.join("update_interval"))
+            .and_then(|ms| ms.trim().parse::<u64>().ok())
+            .map(Duration::from_millis);
         let mut c = Component {
             temperature: 0f32,
+            temperature_millidegrees: 0,
             label: label,
             input_file: input_path.to_path_buf(),
+            input_handle: None,
+            chip_name: chip_name,
+            device_path: device_path,
+            update_interval: update_interval,
+            read_at: None,
             max: max.unwrap_or(0.0),
             critical: critical,
         };
@@ -105,18 +195,107 @@ impl Component {
         c
     }
 
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the name of the hwmon chip this component belongs to (`coretemp` for example),
+    /// or the type of its thermal zone (`cpu-thermal` for example).
+    pub fn get_chip_name(&self) -> &str {
+        &self.chip_name
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the sysfs folder of the hwmon chip or of the thermal zone this component belongs
+    /// to.
+    pub fn get_device_path(&self) -> &Path {
+        &self.device_path
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns how often the chip of this component updates its values, read from its
+    /// `update_interval` attribute when the component is created. Reading the sensor more often
+    /// returns the same value. `None` if the chip doesn't provide it (thermal zones never do).
+    pub fn get_update_interval(&self) -> Option<Duration> {
+        self.update_interval
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the chip updated its values since this component was last read, so
+    /// that updating it would return a new value. Always `true` when the update interval is
+    /// unknown.
+    pub fn is_stale(&self) -> bool {
+        match (self.update_interval, self.read_at) {
+            (Some(interval), Some(read_at)) => read_at.elapsed() >= interval,
+            _ => true,
+        }
+    }
+
+    /// Returns the temperature of the component (in celsius).
+    pub fn get_temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the temperature of the component (in millidegrees celsius) as read from the
+    /// sensor file, without going through a float.
+    pub fn get_temperature_millidegrees(&self) -> i64 {
+        self.temperature_millidegrees
+    }
+
     /// Updates the component.
     pub fn update(&mut self) {
-        let content = get_file_line(self.input_file.as_path()).unwrap();
-        self.temperature = content.replace("\n", "").parse::<f32>().unwrap() / 1000f32;
-        if self.temperature > self.max {
-            self.max = self.temperature;
+        if let Some(temperature) = self.read_temperature() {
+            self.temperature_millidegrees = temperature;
+            self.temperature = temperature as f32 / 1000f32;
+            if self.temperature > self.max {
+                self.max = self.temperature;
+            }
         }
     }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Updates all the given components. Each sensor file is kept opened between two updates
+    /// and read with a single `pread` call, which matters when polling a lot of sensors often.
+    /// Components whose chip didn't update its values since they were last read are skipped
+    /// (take a look at [`is_stale`]).
+    ///
+    /// [`is_stale`]: #method.is_stale
+    pub fn update_all(components: &mut [Component]) {
+        for component in components.iter_mut().filter(|c| c.is_stale()) {
+            component.update();
+        }
+    }
+
+    // Returns the temperature in millidegrees.
+    fn read_temperature(&mut self) -> Option<i64> {
+        if self.input_handle.is_none() {
+            self.input_handle = File::open(&self.input_file).ok();
+        }
+        let mut buf = [0u8; 32];
+        // Reading a sysfs file from the start gives an up-to-date value.
+        count_file_read();
+        let size = match self.input_handle.as_ref().map(|f| f.read_at(&mut buf, 0)) {
+            Some(Ok(size)) => size,
+            _ => {
+                // The sensor might have been removed and added back.
+                self.input_handle = None;
+                return None;
+            }
+        };
+        self.read_at = Some(Instant::now());
+        ::std::str::from_utf8(&buf[..size]).ok()
+            .and_then(|s| s.trim().parse::<i64>().ok())
+    }
 }
 
 pub fn get_components() -> Vec<Component> {
     let mut ret = Vec::new();
+    let mut gpu_folders = Vec::new();
+    append_gpu_files(&mut ret, &mut gpu_folders);
     if let Ok(dir) = read_dir(&Path::new("/sys/class/hwmon/")) {
         for entry in dir {
             if let Ok(entry) = entry {
@@ -125,10 +304,15 @@ pub fn get_components() -> Vec<Component> {
                                             .unwrap_or("").starts_with("hwmon") {
                     continue;
                 }
-                append_files(&mut ret, &entry);
+                // GPUs' sensors have already been added.
+                if canonicalize(&entry).map(|e| gpu_folders.contains(&e)).unwrap_or(false) {
+                    continue;
+                }
+                append_files(&mut ret, &entry, None);
             }
         }
     }
+    append_thermal_zones(&mut ret);
     ret.sort_by(|c1, c2| c1.label.to_lowercase().cmp(&c2.label.to_lowercase()));
     ret
 }
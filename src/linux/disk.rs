@@ -8,12 +8,17 @@ use ::DiskExt;
 use ::utils;
 use super::system::get_all_data;
 
-use libc::statvfs;
+use libc::{statvfs, ST_RDONLY};
 use std::{mem, str};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, read_dir};
+use std::str::FromStr;
+use std::time::Instant;
 use std::fmt::{Debug, Error, Formatter};
 use std::path::{Path, PathBuf};
 use std::ffi::{OsStr, OsString};
 use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
 
 /// Enum containing the different handled disks types.
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -36,15 +41,248 @@ impl From<isize> for DiskType {
     }
 }
 
-fn find_type_for_name(name: &OsStr) -> DiskType
-{
-    #![allow(or_fun_call)]
-    /* turn "sda1" into "sda": */
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing where the data of a disk actually lives.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DiskKind {
+    /// Local block device.
+    Physical,
+    /// Remote file system (NFS, CIFS, etc).
+    Network,
+    /// File system in userspace.
+    Fuse,
+    /// Local block device flagged as removable (USB stick, SD card, etc).
+    RemovableMedia,
+    /// Overlay file system, stacking directories (used by container engines).
+    Overlay,
+}
+
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum used to select which mounts are listed as disks.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DiskListKind {
+    /// Only physical devices (the default).
+    Physical,
+    /// Physical devices, network, FUSE and overlay mounts.
+    All,
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing an entry of the mount table (`/proc/mounts`).
+#[derive(Debug, Clone)]
+pub struct MountInfo {
+    /// Mounted device (or any string for pseudo file systems, like "proc" or "tmpfs").
+    pub device: String,
+    /// Where the device is mounted.
+    pub mount_point: PathBuf,
+    /// File system type ("ext4", "tmpfs", etc).
+    pub file_system: String,
+    /// Comma-separated mount options, like "rw,relatime".
+    pub options: String,
+}
+
+// Spaces, tabs, newlines and backslashes are written as octal escapes ("\040" for a space).
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4)
+                         .filter(|o| bytes[i] == b'\\' && o.iter().all(|b| b'0' <= *b && *b <= b'7'));
+        if let Some(o) = octal {
+            ret.push((o[0] - b'0') * 64 + (o[1] - b'0') * 8 + o[2] - b'0');
+            i += 4;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+// Parses the content of a `/proc/mounts` file.
+pub fn parse_mounts(data: &str) -> Vec<MountInfo> {
+    let mut ret = Vec::new();
+    for line in data.lines() {
+        let mut split = line.split_whitespace();
+        if let (Some(device), Some(mount_point), Some(file_system), Some(options)) =
+                (split.next(), split.next(), split.next(), split.next()) {
+            ret.push(MountInfo {
+                device: unescape_mount_field(device),
+                mount_point: PathBuf::from(unescape_mount_field(mount_point)),
+                file_system: file_system.to_owned(),
+                options: options.to_owned(),
+            });
+        }
+    }
+    ret
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing a block device listed in `/sys/block`, whether it is mounted or not.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDevice {
+    /// Name of the device ("sda", "sda1", "nvme0n1p1", etc).
+    pub name: String,
+    /// Name of the device this partition belongs to. `None` for whole devices.
+    pub parent: Option<String>,
+    /// Size of the device (in bytes).
+    pub size: u64,
+    /// Whether the device is in use by a mounted file system: it's mounted itself, whatever the
+    /// path used to mount it (`/dev/mapper/*`, `/dev/disk/by-uuid/*`, etc), or one of its
+    /// partitions is, or one of the devices built on top of it (LVM or RAID volumes for
+    /// example) is.
+    pub mounted: bool,
+}
+
+// Turns the "major:minor" content of a sysfs `dev` file into a device number, encoded like
+// glibc's `makedev` does.
+fn parse_device_number(data: &str) -> Option<u64> {
+    let mut parts = data.trim().splitn(2, ':');
+    let major = u64::from_str(parts.next()?).ok()?;
+    let minor = u64::from_str(parts.next()?).ok()?;
+    Some(((major & 0xffff_f000) << 32) | ((major & 0xfff) << 8) |
+         ((minor & 0xffff_ff00) << 12) | (minor & 0xff))
+}
+
+// Returns the device numbers of the mounted block devices. The mount table can name them
+// through links (`/dev/mapper/*`, `/dev/disk/by-uuid/*`, etc), so they're compared by number.
+fn get_mounted_device_numbers(mounts: &[MountInfo]) -> HashSet<u64> {
+    mounts.iter()
+          .filter_map(|m| Path::new(&m.device).canonicalize().ok())
+          .filter_map(|device| fs::metadata(device).ok())
+          .filter(|metadata| metadata.file_type().is_block_device())
+          .map(|metadata| metadata.rdev())
+          .collect()
+}
+
+// A device is in use if it's mounted, or if one of the devices built on top of it (its
+// "holders": device mapper or md devices, for LVM or RAID for example) is.
+fn is_device_mounted(folder: &Path, mounted: &HashSet<u64>, depth: usize) -> bool {
+    let device_number = get_all_data(folder.join("dev")).ok()
+                                                        .and_then(|d| parse_device_number(&d));
+    if let Some(device_number) = device_number {
+        if mounted.contains(&device_number) {
+            return true;
+        }
+    }
+    // Holders can be stacked (LVM on top of RAID for example), but not endlessly.
+    if depth >= 8 {
+        return false;
+    }
+    match read_dir(folder.join("holders")) {
+        Ok(dir) => dir.filter_map(|e| e.ok())
+                      .any(|e| is_device_mounted(&Path::new("/sys/block").join(e.file_name()),
+                                                 mounted, depth + 1)),
+        Err(_) => false,
+    }
+}
+
+fn new_block_device(folder: &Path, name: &str, parent: Option<&str>,
+                    mounted: bool) -> BlockDevice {
+    // The size is always given in 512-byte sectors, whatever the device's sector size is.
+    let size = get_all_data(folder.join("size")).ok()
+                                                .and_then(|s| u64::from_str(s.trim()).ok())
+                                                .unwrap_or(0);
+    BlockDevice {
+        name: name.to_owned(),
+        parent: parent.map(|p| p.to_owned()),
+        size: size * 512,
+        mounted: mounted,
+    }
+}
+
+// Returns the block devices of `/sys/block` and their partitions, each device being followed
+// by its partitions.
+pub fn get_block_devices(mounts: &[MountInfo]) -> Vec<BlockDevice> {
+    let mut ret = Vec::new();
+    let mounted = get_mounted_device_numbers(mounts);
+    let mut names: Vec<String> = match read_dir("/sys/block") {
+        Ok(dir) => dir.filter_map(|e| e.ok())
+                      .filter_map(|e| e.file_name().to_str().map(|n| n.to_owned()))
+                      .collect(),
+        Err(_) => return ret,
+    };
+    names.sort();
+    for name in names {
+        let folder = Path::new("/sys/block").join(&name);
+        // Partitions are the sub-folders containing a "partition" file.
+        let mut partitions: Vec<String> = match read_dir(&folder) {
+            Ok(dir) => dir.filter_map(|e| e.ok())
+                          .filter(|e| e.path().join("partition").exists())
+                          .filter_map(|e| e.file_name().to_str().map(|n| n.to_owned()))
+                          .collect(),
+            Err(_) => Vec::new(),
+        };
+        partitions.sort();
+        let partitions: Vec<BlockDevice> = partitions.iter().map(|partition| {
+            let partition_folder = folder.join(partition);
+            let partition_mounted = is_device_mounted(&partition_folder, &mounted, 0);
+            new_block_device(&partition_folder, partition, Some(&name), partition_mounted)
+        }).collect();
+        // A disk is in use as soon as one of its partitions is.
+        let disk_mounted = partitions.iter().any(|p| p.mounted) ||
+                           is_device_mounted(&folder, &mounted, 0);
+        ret.push(new_block_device(&folder, &name, None, disk_mounted));
+        ret.extend(partitions);
+    }
+    ret
+}
+
+const NETWORK_FILE_SYSTEMS: &[&str] = &["nfs", "nfs4", "cifs", "smbfs", "smb3", "9p", "afs",
+                                        "ceph", "glusterfs"];
+
+pub fn find_kind_for_mount(device: &str, file_system: &str) -> Option<DiskKind> {
+    if NETWORK_FILE_SYSTEMS.contains(&file_system) {
+        Some(DiskKind::Network)
+    } else if file_system == "fuse" || file_system.starts_with("fuse.") {
+        Some(DiskKind::Fuse)
+    } else if file_system == "overlay" {
+        Some(DiskKind::Overlay)
+    } else if device.starts_with("/dev/sd") {
+        if is_removable(OsStr::new(&device[5..])) {
+            Some(DiskKind::RemovableMedia)
+        } else {
+            Some(DiskKind::Physical)
+        }
+    } else {
+        None
+    }
+}
+
+// Returns the UUID of the btrfs file system `device` belongs to, as listed in `/sys/fs/btrfs`.
+pub fn get_btrfs_fsid(device: &str) -> Option<OsString> {
+    // `/dev/mapper/*` devices are links to `/dev/dm-*`.
+    let device = Path::new(device).canonicalize().ok()?;
+    let name = device.file_name()?;
+    read_dir("/sys/fs/btrfs").ok()?
+                             .filter_map(|e| e.ok())
+                             .find(|e| e.path().join("devices").join(name).exists())
+                             .map(|e| e.file_name())
+}
+
+/* turn "sda1" into "sda": */
+fn trim_partition_number(name: &OsStr) -> &OsStr {
     let mut trimmed: &[u8] = name.as_bytes();
     while trimmed.len() > 1 && trimmed[trimmed.len()-1] >= b'0' && trimmed[trimmed.len()-1] <= b'9' {
         trimmed = &trimmed[..trimmed.len()-1]
     }
-    let trimmed: &OsStr = OsStrExt::from_bytes(trimmed);
+    OsStrExt::from_bytes(trimmed)
+}
+
+fn is_removable(name: &OsStr) -> bool {
+    let path = Path::new("/sys/block/").join(trim_partition_number(name)).join("removable");
+    get_all_data(path).map(|s| s.trim() == "1").unwrap_or(false)
+}
+
+fn find_type_for_name(name: &OsStr) -> DiskType
+{
+    #![allow(or_fun_call)]
+    let trimmed = trim_partition_number(name);
 
     let path = Path::new("/sys/block/").to_owned()
         .join(trimmed)
@@ -53,36 +291,205 @@ fn find_type_for_name(name: &OsStr) -> DiskType
     DiskType::from(rotational_int.unwrap_or(-1))
 }
 
-pub fn new(name: &OsStr, mount_point: &Path, file_system: &[u8]) -> Disk {
+pub fn new(name: &OsStr, mount_point: &Path, file_system: &[u8], kind: DiskKind,
+           mount_options: &str) -> Disk {
     let mount_point_cpath = utils::to_cpath(mount_point);
     let type_ = find_type_for_name(name);
+    let mount_options: Vec<String> = mount_options.split(',').map(|o| o.to_owned()).collect();
+    let mut read_only = mount_options.iter().any(|o| o == "ro");
     let mut total_space = 0;
     let mut available_space = 0;
+    let mut free_space = 0;
+    let mut total_inodes = 0;
+    let mut available_inodes = 0;
     unsafe {
         let mut stat: statvfs = mem::zeroed();
         if statvfs(mount_point_cpath.as_ptr() as *const i8, &mut stat) == 0 {
             total_space = stat.f_bsize * stat.f_blocks;
             available_space = stat.f_bsize * stat.f_bavail;
+            free_space = stat.f_bsize * stat.f_bfree;
+            total_inodes = stat.f_files;
+            available_inodes = stat.f_ffree;
+            read_only = stat.f_flag & ST_RDONLY != 0;
         }
     }
+    let device_id = fs::metadata(mount_point).ok().map(|m| m.dev());
     Disk {
         type_: type_,
+        kind: kind,
         name: name.to_owned(),
         file_system: file_system.to_owned(),
         mount_point: mount_point.to_owned(),
         total_space: total_space,
         available_space: available_space,
+        free_space: free_space,
+        total_inodes: total_inodes,
+        available_inodes: available_inodes,
+        mount_options: mount_options,
+        read_only: read_only,
+        failed_updates: 0,
+        io_time_ms: 0,
+        weighted_io_time_ms: 0,
+        io_times_read_at: None,
+        average_queue_depth: 0.,
+        device_id: device_id,
     }
 }
 
+// Records the result of an update to know how many of them failed in a row.
+pub fn record_update(disk: &mut Disk, success: bool) {
+    disk.failed_updates = if success { 0 } else { disk.failed_updates + 1 };
+}
+
+pub fn get_failed_updates(disk: &Disk) -> u32 {
+    disk.failed_updates
+}
+
+// Returns the device id of the mount point, read once when the disk was created.
+pub fn get_device_id(disk: &Disk) -> Option<u64> {
+    disk.device_id
+}
+
+// Returns the time spent doing I/O and the weighted time spent doing I/O (both in milliseconds)
+// of each device of a `/proc/diskstats` file.
+pub fn parse_diskstats(data: &str) -> HashMap<String, (u64, u64)> {
+    let mut ret = HashMap::new();
+    for line in data.lines() {
+        // "major minor name" followed by the statistics: the I/O times are the 10th and 11th.
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 14 {
+            continue;
+        }
+        if let (Ok(io_time), Ok(weighted_io_time)) = (u64::from_str(parts[12]),
+                                                      u64::from_str(parts[13])) {
+            ret.insert(parts[2].to_owned(), (io_time, weighted_io_time));
+        }
+    }
+    ret
+}
+
+// Updates the I/O times of the disk and computes the average queue depth since the previous
+// update.
+pub fn set_io_times(disk: &mut Disk, io_time_ms: u64, weighted_io_time_ms: u64) {
+    let now = Instant::now();
+    if let Some(read_at) = disk.io_times_read_at {
+        let elapsed = now.duration_since(read_at);
+        let elapsed_ms = elapsed.as_secs() as f32 * 1000. + elapsed.subsec_nanos() as f32 / 1e6;
+        let weighted = weighted_io_time_ms.saturating_sub(disk.weighted_io_time_ms);
+        disk.average_queue_depth = if elapsed_ms > 0. { weighted as f32 / elapsed_ms } else { 0. };
+    }
+    disk.io_time_ms = io_time_ms;
+    disk.weighted_io_time_ms = weighted_io_time_ms;
+    disk.io_times_read_at = Some(now);
+}
+
 /// Struct containing a disk information.
 pub struct Disk {
     type_: DiskType,
+    kind: DiskKind,
     name: OsString,
     file_system: Vec<u8>,
     mount_point: PathBuf,
     total_space: u64,
     available_space: u64,
+    free_space: u64,
+    total_inodes: u64,
+    available_inodes: u64,
+    mount_options: Vec<String>,
+    read_only: bool,
+    failed_updates: u32,
+    io_time_ms: u64,
+    weighted_io_time_ms: u64,
+    io_times_read_at: Option<Instant>,
+    average_queue_depth: f32,
+    device_id: Option<u64>,
+}
+
+impl Disk {
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns where the data of this disk lives (local device, network, FUSE, etc).
+    pub fn get_kind(&self) -> DiskKind {
+        self.kind
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the options the disk was mounted with, like `["rw", "relatime"]`.
+    pub fn get_mount_options(&self) -> &[String] {
+        &self.mount_options
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the read-only layers of an overlay disk (its `lowerdir` mount option), the
+    /// topmost one first. Empty for the other kinds of disks.
+    pub fn get_overlay_lower_dirs(&self) -> Vec<PathBuf> {
+        self.get_overlay_option("lowerdir=")
+            .map(|dirs| dirs.split(':').filter(|d| !d.is_empty()).map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the writable layer of an overlay disk (its `upperdir` mount option). `None` for
+    /// read-only overlays and the other kinds of disks.
+    pub fn get_overlay_upper_dir(&self) -> Option<PathBuf> {
+        self.get_overlay_option("upperdir=").map(PathBuf::from)
+    }
+
+    fn get_overlay_option(&self, prefix: &str) -> Option<&str> {
+        if self.kind != DiskKind::Overlay {
+            return None;
+        }
+        self.mount_options.iter().find(|o| o.starts_with(prefix)).map(|o| &o[prefix.len()..])
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the disk is mounted read-only. Unlike [`get_mount_options`], it's
+    /// updated by [`DiskExt::update`], so it also reports disks remounted read-only after a file
+    /// system error.
+    ///
+    /// [`get_mount_options`]: #method.get_mount_options
+    /// [`DiskExt::update`]: trait.DiskExt.html#tymethod.update
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the time (in milliseconds) the device spent doing I/O since the boot, read from
+    /// `/proc/diskstats` by [`SystemExt::refresh_disks`]. It is `0` for disks which aren't
+    /// block devices (network or virtual file systems for example).
+    ///
+    /// [`SystemExt::refresh_disks`]: trait.SystemExt.html#tymethod.refresh_disks
+    pub fn get_io_time_ms(&self) -> u64 {
+        self.io_time_ms
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the average number of I/O requests waiting or being served by the device between
+    /// the two last calls to [`SystemExt::refresh_disks`], computed from the weighted I/O time
+    /// of `/proc/diskstats`. A value staying above the number of requests the device can serve
+    /// at once means it is saturated.
+    ///
+    /// [`SystemExt::refresh_disks`]: trait.SystemExt.html#tymethod.refresh_disks
+    pub fn get_average_queue_depth(&self) -> f32 {
+        self.average_queue_depth
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the free disk size, in bytes, including the blocks reserved for the root user
+    /// (usually 5% on ext4). Unprivileged users can only use [`get_available_space`], so a disk
+    /// can be full for them while this still returns a non-zero value.
+    ///
+    /// [`get_available_space`]: trait.DiskExt.html#tymethod.get_available_space
+    pub fn get_free_space(&self) -> u64 {
+        self.free_space
+    }
 }
 
 impl Debug for Disk {
@@ -119,12 +526,23 @@ impl DiskExt for Disk {
         self.available_space
     }
 
+    fn get_total_inodes(&self) -> u64 {
+        self.total_inodes
+    }
+
+    fn get_available_inodes(&self) -> u64 {
+        self.available_inodes
+    }
+
     fn update(&mut self) -> bool {
         unsafe {
             let mut stat: statvfs = mem::zeroed();
             let mount_point_cpath = utils::to_cpath(&self.mount_point);
             if statvfs(mount_point_cpath.as_ptr() as *const i8, &mut stat) == 0 {
                 self.available_space = stat.f_bsize * stat.f_bavail;
+                self.free_space = stat.f_bsize * stat.f_bfree;
+                self.available_inodes = stat.f_ffree;
+                self.read_only = stat.f_flag & ST_RDONLY != 0;
                 true
             } else {
                 false
@@ -132,3 +550,15 @@ impl DiskExt for Disk {
         }
     }
 }
+
+#[test]
+fn test_parse_device_number() {
+    assert_eq!(parse_device_number("8:1\n"), Some(0x801));
+    assert_eq!(parse_device_number("259:0"), Some(0x10300));
+    // Minor numbers above 255 are split.
+    assert_eq!(parse_device_number("253:300"), Some(0x10fd2c));
+    assert_eq!(parse_device_number("8"), None);
+    // Same encoding as the kernel's.
+    let null = fs::metadata("/dev/null").expect("no /dev/null");
+    assert_eq!(parse_device_number("1:3"), Some(null.rdev()));
+}
@@ -4,14 +4,23 @@
 // Copyright (c) 2015 Guillaume Gomez
 //
 
+mod cgroup;
 pub mod component;
 pub mod disk;
+pub mod network;
+pub mod numa;
 pub mod process;
 pub mod processor;
 pub mod system;
+mod users;
+pub mod virtualization;
 
 pub use self::component::Component;
-pub use self::disk::{Disk, DiskType};
-pub use self::process::{Process,ProcessStatus};
-pub use self::processor::Processor;
-pub use self::system::System;
+pub use self::disk::{BlockDevice, Disk, DiskKind, DiskListKind, DiskType, MountInfo};
+pub use self::network::{NetworkData, ProtocolStats, TcpConnection, TcpState};
+pub use self::numa::NumaNode;
+pub use self::process::{MemoryMapRegion, Process, ProcessIo, ProcessLimits, ProcessStat,
+                        ProcessStatus, ResourceLimit, SchedulingPolicy};
+pub use self::processor::{CacheInfo, Processor};
+pub use self::system::{CounterMode, ProcReadError, ProcessNameSource, RefreshStats, System};
+pub use self::virtualization::Virtualization;
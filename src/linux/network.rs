@@ -0,0 +1,340 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing the different states of a TCP connection.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TcpState {
+    /// Connection established.
+    Established,
+    /// Connection request sent.
+    SynSent,
+    /// Connection request received.
+    SynRecv,
+    /// Waiting for a termination request or the acknowledgment of the one sent.
+    FinWait1,
+    /// Waiting for a termination request from the remote host.
+    FinWait2,
+    /// Waiting to be sure the remote host received the acknowledgment of its termination request.
+    TimeWait,
+    /// Closed.
+    Close,
+    /// Waiting for a termination request from the local user.
+    CloseWait,
+    /// Waiting for the acknowledgment of the termination request sent.
+    LastAck,
+    /// Waiting for connection requests.
+    Listen,
+    /// Waiting for the acknowledgment of a termination request.
+    Closing,
+    /// Unknown.
+    Unknown(u32),
+}
+
+impl From<u32> for TcpState {
+    fn from(state: u32) -> TcpState {
+        match state {
+            0x01 => TcpState::Established,
+            0x02 => TcpState::SynSent,
+            0x03 => TcpState::SynRecv,
+            0x04 => TcpState::FinWait1,
+            0x05 => TcpState::FinWait2,
+            0x06 => TcpState::TimeWait,
+            0x07 => TcpState::Close,
+            0x08 => TcpState::CloseWait,
+            0x09 => TcpState::LastAck,
+            0x0A => TcpState::Listen,
+            0x0B => TcpState::Closing,
+            x => TcpState::Unknown(x),
+        }
+    }
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing a TCP connection information.
+#[derive(Clone, Debug)]
+pub struct TcpConnection {
+    /// Local address of the connection.
+    pub local_address: SocketAddr,
+    /// Remote address of the connection.
+    pub remote_address: SocketAddr,
+    /// State of the connection.
+    pub state: TcpState,
+    /// Inode of the socket.
+    pub inode: u64,
+}
+
+// Addresses are written as the hexadecimal representation of 32-bits words in the host byte
+// order, followed by the port: "0100007F:0CEA" is "127.0.0.1:3306" on little-endian hosts.
+fn parse_address(s: &str) -> Option<SocketAddr> {
+    let mut parts = s.splitn(2, ':');
+    let (ip, port) = match (parts.next(), parts.next()) {
+        (Some(ip), Some(port)) => (ip, u16::from_str_radix(port, 16).ok()?),
+        _ => return None,
+    };
+    // 8 hexadecimal digits for IPv4, 32 for IPv6.
+    if ip.len() != 8 && ip.len() != 32 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(16);
+    for i in 0..ip.len() / 8 {
+        let word = u32::from_str_radix(ip.get(i * 8..i * 8 + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let ip = if bytes.len() == 4 {
+        IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))
+    } else {
+        let mut octets = [0; 16];
+        octets.copy_from_slice(&bytes);
+        IpAddr::V6(Ipv6Addr::from(octets))
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+// Parses the content of a `/proc/net/tcp` or `/proc/net/tcp6` file.
+pub fn parse_tcp_file(data: &str) -> Vec<TcpConnection> {
+    let mut ret = Vec::new();
+    // The first line contains the columns' names.
+    for line in data.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 10 {
+            continue;
+        }
+        if let (Some(local_address), Some(remote_address), Ok(state), Ok(inode)) =
+                (parse_address(parts[1]), parse_address(parts[2]),
+                 u32::from_str_radix(parts[3], 16), u64::from_str(parts[9])) {
+            ret.push(TcpConnection {
+                local_address: local_address,
+                remote_address: remote_address,
+                state: TcpState::from(state),
+                inode: inode,
+            });
+        }
+    }
+    ret
+}
+
+// Returns the TCP connections of the network namespace of the given `/proc/[pid]` directory.
+pub fn get_tcp_connections(proc_path: &Path) -> Vec<TcpConnection> {
+    let mut ret = Vec::new();
+    for file in &["net/tcp", "net/tcp6"] {
+        // These files can be much bigger than the other `/proc` ones so they're read entirely.
+        if let Ok(data) = read_to_string(proc_path.join(file)) {
+            ret.extend(parse_tcp_file(&data));
+        }
+    }
+    ret
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing the protocol counters of the `/proc/net/snmp` file, by protocol and name
+/// ("RetransSegs" for the TCP retransmitted segments, "InErrors" for the UDP receive errors...).
+/// Most of them are cumulated since the boot, some (like "CurrEstab" or "MaxConn") are gauges.
+#[derive(Clone, Debug, Default)]
+pub struct ProtocolStats {
+    /// IP counters.
+    pub ip: HashMap<String, i64>,
+    /// ICMP counters.
+    pub icmp: HashMap<String, i64>,
+    /// TCP counters.
+    pub tcp: HashMap<String, i64>,
+    /// UDP counters.
+    pub udp: HashMap<String, i64>,
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing the traffic of a network interface, read from `/proc/net/dev` by
+/// [`System::refresh_networks`].
+///
+/// [`System::refresh_networks`]: struct.System.html#method.refresh_networks
+#[derive(Clone, Debug, Default)]
+pub struct NetworkData {
+    received: u64,
+    transmitted: u64,
+    total_received: u64,
+    total_transmitted: u64,
+    elapsed_secs: f32,
+}
+
+impl NetworkData {
+    /// Returns the number of bytes received between the two last refreshes.
+    pub fn get_received(&self) -> u64 {
+        self.received
+    }
+
+    /// Returns the number of bytes sent between the two last refreshes.
+    pub fn get_transmitted(&self) -> u64 {
+        self.transmitted
+    }
+
+    /// Returns the number of bytes received per second between the two last refreshes. It is
+    /// divided by the time which actually elapsed between them, so irregular refreshes still
+    /// give an accurate rate.
+    pub fn get_received_rate(&self) -> f32 {
+        rate(self.received, self.elapsed_secs)
+    }
+
+    /// Returns the number of bytes sent per second between the two last refreshes. It is
+    /// divided by the time which actually elapsed between them, so irregular refreshes still
+    /// give an accurate rate.
+    pub fn get_transmitted_rate(&self) -> f32 {
+        rate(self.transmitted, self.elapsed_secs)
+    }
+
+    /// Returns the number of bytes received since the interface was brought up.
+    pub fn get_total_received(&self) -> u64 {
+        self.total_received
+    }
+
+    /// Returns the number of bytes sent since the interface was brought up.
+    pub fn get_total_transmitted(&self) -> u64 {
+        self.total_transmitted
+    }
+}
+
+fn rate(bytes: u64, elapsed_secs: f32) -> f32 {
+    if elapsed_secs > 0. { bytes as f32 / elapsed_secs } else { 0. }
+}
+
+// Returns the received and sent bytes of each interface of a `/proc/net/dev` file.
+pub fn parse_net_dev(data: &str) -> HashMap<String, (u64, u64)> {
+    let mut ret = HashMap::new();
+    // The two first lines are headers.
+    for line in data.lines().skip(2) {
+        let mut parts = line.splitn(2, ':');
+        if let (Some(name), Some(values)) = (parts.next(), parts.next()) {
+            // The received bytes are the 1st value and the sent ones the 9th.
+            let values: Vec<&str> = values.split_whitespace().collect();
+            if values.len() < 9 {
+                continue;
+            }
+            if let (Ok(received), Ok(transmitted)) = (u64::from_str(values[0]),
+                                                      u64::from_str(values[8])) {
+                ret.insert(name.trim().to_owned(), (received, transmitted));
+            }
+        }
+    }
+    ret
+}
+
+// Updates the interfaces with the counters read `elapsed_secs` after the previous ones. The
+// interfaces which disappeared are removed and the new ones start with no traffic.
+pub fn update_networks(networks: &mut HashMap<String, NetworkData>,
+                       counters: HashMap<String, (u64, u64)>, elapsed_secs: f32) {
+    networks.retain(|name, _| counters.contains_key(name));
+    for (name, (received, transmitted)) in counters {
+        let network = networks.entry(name).or_insert_with(|| NetworkData {
+            total_received: received,
+            total_transmitted: transmitted,
+            ..NetworkData::default()
+        });
+        network.received = received.saturating_sub(network.total_received);
+        network.transmitted = transmitted.saturating_sub(network.total_transmitted);
+        network.total_received = received;
+        network.total_transmitted = transmitted;
+        network.elapsed_secs = elapsed_secs;
+    }
+}
+
+// Parses the content of a `/proc/net/snmp` file.
+pub fn parse_snmp_file(data: &str) -> ProtocolStats {
+    let mut stats = ProtocolStats::default();
+    let mut lines = data.lines();
+    // Each protocol has two lines: "Tcp: RtoAlgorithm RtoMin..." followed by "Tcp: 1 200...".
+    while let (Some(names), Some(values)) = (lines.next(), lines.next()) {
+        let mut names = names.split_whitespace();
+        let mut values = values.split_whitespace();
+        let section = match names.next() {
+            Some("Ip:") => &mut stats.ip,
+            Some("Icmp:") => &mut stats.icmp,
+            Some("Tcp:") => &mut stats.tcp,
+            Some("Udp:") => &mut stats.udp,
+            _ => continue,
+        };
+        values.next();
+        for (name, value) in names.zip(values) {
+            if let Ok(value) = i64::from_str(value) {
+                section.insert(name.to_owned(), value);
+            }
+        }
+    }
+    stats
+}
+
+// The addresses of the fixtures were written on a little-endian host.
+#[cfg(target_endian = "little")]
+#[test]
+fn test_parse_tcp_file() {
+    let connections = parse_tcp_file(include_str!("../../tests/fixtures/net_tcp"));
+    assert_eq!(connections.len(), 2);
+    assert_eq!(connections[0].local_address, "127.0.0.1:3306".parse().unwrap());
+    assert_eq!(connections[0].remote_address, "0.0.0.0:0".parse().unwrap());
+    assert_eq!(connections[0].state, TcpState::Listen);
+    assert_eq!(connections[0].inode, 18537);
+    assert_eq!(connections[1].local_address, "10.0.2.15:22".parse().unwrap());
+    assert_eq!(connections[1].remote_address, "10.0.2.2:54198".parse().unwrap());
+    assert_eq!(connections[1].state, TcpState::Established);
+
+    let connections = parse_tcp_file(include_str!("../../tests/fixtures/net_tcp6"));
+    assert_eq!(connections.len(), 2);
+    assert_eq!(connections[0].local_address, "[::1]:631".parse().unwrap());
+    assert_eq!(connections[0].state, TcpState::Listen);
+    assert_eq!(connections[1].local_address, "[::ffff:127.0.0.1]:8080".parse().unwrap());
+    assert_eq!(connections[1].remote_address, "[::ffff:127.0.0.1]:50594".parse().unwrap());
+    assert_eq!(connections[1].state, TcpState::TimeWait);
+    assert_eq!(connections[1].inode, 0);
+}
+
+#[test]
+fn test_parse_address() {
+    assert_eq!(parse_address("0100007F"), None);
+    assert_eq!(parse_address("0100007F:XYZ"), None);
+    // Neither IPv4 nor IPv6.
+    assert_eq!(parse_address("0100007F0100:0016"), None);
+}
+
+#[test]
+fn test_parse_net_dev() {
+    let counters = parse_net_dev(include_str!("../../tests/fixtures/net_dev"));
+    assert_eq!(counters.len(), 3);
+    assert_eq!(counters["lo"], (186718155, 186718155));
+    assert_eq!(counters["eth0"], (1332865, 27855));
+    // There is no space after the colon when the value is too long.
+    assert_eq!(counters["wlan0"], (12345678901, 987654321));
+}
+
+#[test]
+fn test_update_networks() {
+    let mut networks = HashMap::new();
+    let mut counters = HashMap::new();
+    counters.insert("eth0".to_owned(), (1000, 500));
+    counters.insert("wlan0".to_owned(), (200, 100));
+    // The first sample gives no traffic: the counters are since the interface was brought up.
+    update_networks(&mut networks, counters, 0.);
+    assert_eq!(networks["eth0"].get_received(), 0);
+    assert_eq!(networks["eth0"].get_total_received(), 1000);
+    assert_eq!(networks["eth0"].get_received_rate(), 0.);
+
+    let mut counters = HashMap::new();
+    counters.insert("eth0".to_owned(), (3000, 1500));
+    update_networks(&mut networks, counters, 2.);
+    assert_eq!(networks["eth0"].get_received(), 2000);
+    assert_eq!(networks["eth0"].get_transmitted(), 1000);
+    assert_eq!(networks["eth0"].get_received_rate(), 1000.);
+    assert_eq!(networks["eth0"].get_transmitted_rate(), 500.);
+    // The interface was removed.
+    assert!(!networks.contains_key("wlan0"));
+}
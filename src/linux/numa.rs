@@ -0,0 +1,83 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::fs::read_dir;
+use std::str::FromStr;
+
+use super::system::get_all_data;
+
+/// Struct containing a NUMA node memory information.
+#[derive(Debug, Clone)]
+pub struct NumaNode {
+    id: u32,
+    mem_total: u64,
+    mem_free: u64,
+}
+
+impl NumaNode {
+    /// Returns the node's id (`0` for `node0` for example).
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the RAM size attached to this node (in kB).
+    pub fn get_total_memory(&self) -> u64 {
+        self.mem_total
+    }
+
+    /// Returns the free RAM size on this node (in kB).
+    pub fn get_free_memory(&self) -> u64 {
+        self.mem_free
+    }
+
+    /// Returns the used RAM size on this node (in kB).
+    pub fn get_used_memory(&self) -> u64 {
+        self.mem_total.saturating_sub(self.mem_free)
+    }
+}
+
+fn parse_node_meminfo(id: u32, data: &str) -> NumaNode {
+    let mut node = NumaNode {
+        id: id,
+        mem_total: 0,
+        mem_free: 0,
+    };
+
+    // Lines look like "Node 0 MemTotal:       16318156 kB".
+    for line in data.lines() {
+        let mut parts = line.split_whitespace().skip(2);
+        let field = match parts.next() {
+            Some("MemTotal:") => &mut node.mem_total,
+            Some("MemFree:") => &mut node.mem_free,
+            _ => continue,
+        };
+        if let Some(Ok(val)) = parts.next().map(u64::from_str) {
+            *field = val;
+        }
+    }
+    node
+}
+
+pub fn get_numa_nodes() -> Vec<NumaNode> {
+    let mut ret = Vec::new();
+    if let Ok(dir) = read_dir("/sys/devices/system/node/") {
+        for entry in dir.filter_map(|e| e.ok()) {
+            let entry = entry.path();
+            let id = match entry.file_name().and_then(|x| x.to_str()) {
+                Some(name) if name.starts_with("node") => match u32::from_str(&name[4..]) {
+                    Ok(id) => id,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+            if let Ok(data) = get_all_data(entry.join("meminfo")) {
+                ret.push(parse_node_meminfo(id, &data));
+            }
+        }
+    }
+    ret.sort_by_key(|n| n.id);
+    ret
+}
@@ -6,12 +6,21 @@
 
 use std::fmt::{self, Formatter, Debug};
 use std::collections::HashMap;
+use std::fs::{read_dir, read_link};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::str::FromStr;
 use libc::{c_int, gid_t, kill, pid_t, uid_t};
 
 use ::ProcessExt;
+use sys::cgroup::find_main_cgroup_path;
+use sys::network::{get_tcp_connections, TcpConnection};
+use sys::system::{compute_refreshed_process_cpu_usage, copy_from_file, get_all_data,
+                  get_clock_ticks, get_start_ticks, parse_stat_file, refresh_process_data,
+                  CounterMode, ProcReadError, ProcessNameSource, ProcessRefreshSettings};
 
 /// Enum describing the different status of a process.
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ProcessStatus {
     /// Waiting in uninterruptible disk sleep.
     Idle,
@@ -33,7 +42,11 @@ pub enum ProcessStatus {
     Waking,
     /// Parked (Linux 3.9 to 3.13 only).
     Parked,
-    /// Unknown.
+    /// Idle kernel thread (Linux 4.14 onward). Unlike [`Idle`], it isn't waiting for anything.
+    ///
+    /// [`Idle`]: #variant.Idle
+    KernelIdle,
+    /// Unknown. For the states read from `/proc`, contains the code of the state character.
     Unknown(u32),
 }
 
@@ -63,6 +76,7 @@ impl From<char> for ProcessStatus {
             'K' => ProcessStatus::Wakekill,
             'W' => ProcessStatus::Waking,
             'P' => ProcessStatus::Parked,
+            'I' => ProcessStatus::KernelIdle,
             x   => ProcessStatus::Unknown(x as u32),
         }
     }
@@ -82,6 +96,7 @@ impl ProcessStatus {
             ProcessStatus::Wakekill   => "Wakekill",
             ProcessStatus::Waking     => "Waking",
             ProcessStatus::Parked     => "Parked",
+            ProcessStatus::KernelIdle => "Idle kernel thread",
             ProcessStatus::Unknown(_) => "Unknown",
         }
     }
@@ -93,6 +108,244 @@ impl fmt::Display for ProcessStatus {
     }
 }
 
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing the scheduling policy of a process.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SchedulingPolicy {
+    /// Default time-sharing scheduling (`SCHED_OTHER`).
+    Other,
+    /// Real-time first-in first-out scheduling (`SCHED_FIFO`).
+    Fifo,
+    /// Real-time round-robin scheduling (`SCHED_RR`).
+    RoundRobin,
+    /// Time-sharing scheduling for CPU-bound processes (`SCHED_BATCH`).
+    Batch,
+    /// Scheduling for very low priority background jobs (`SCHED_IDLE`).
+    Idle,
+    /// Deadline scheduling (`SCHED_DEADLINE`).
+    Deadline,
+    /// Unknown.
+    Unknown(u32),
+}
+
+impl From<u32> for SchedulingPolicy {
+    fn from(policy: u32) -> SchedulingPolicy {
+        match policy {
+            0 => SchedulingPolicy::Other,
+            1 => SchedulingPolicy::Fifo,
+            2 => SchedulingPolicy::RoundRobin,
+            3 => SchedulingPolicy::Batch,
+            5 => SchedulingPolicy::Idle,
+            6 => SchedulingPolicy::Deadline,
+            x => SchedulingPolicy::Unknown(x),
+        }
+    }
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Lightweight summary of a process, built only from its `/proc/[pid]/stat` file. Take a look at
+/// [`System::for_each_process`].
+///
+/// [`System::for_each_process`]: struct.System.html#method.for_each_process
+#[derive(Clone, Debug)]
+pub struct ProcessStat {
+    /// Pid of the process.
+    pub pid: pid_t,
+    /// Pid of the parent process.
+    pub parent: Option<pid_t>,
+    /// Name of the program, as stored by the kernel (truncated to 15 characters).
+    pub comm: String,
+    /// Status of the process.
+    pub status: Option<ProcessStatus>,
+    /// Time spent in user mode (in clock ticks).
+    pub utime: u64,
+    /// Time spent in kernel mode (in clock ticks).
+    pub stime: u64,
+    /// Memory usage (in kB).
+    pub memory: u64,
+    /// Time of process launch (in seconds since boot).
+    pub start_time: u64,
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Soft and hard values of a resource limit. `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ResourceLimit {
+    /// Value enforced by the kernel, which the process can raise up to `hard`.
+    pub soft: Option<u64>,
+    /// Ceiling for `soft`.
+    pub hard: Option<u64>,
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Resource limits of a process, as listed in `/proc/[pid]/limits`. Take a look at
+/// [`Process::get_limits`].
+///
+/// [`Process::get_limits`]: struct.Process.html#method.get_limits
+#[derive(Clone, Debug, Default)]
+pub struct ProcessLimits {
+    /// CPU time (in seconds).
+    pub cpu_time: ResourceLimit,
+    /// Size of the files the process can create (in bytes).
+    pub file_size: ResourceLimit,
+    /// Size of the data segment (in bytes).
+    pub data_size: ResourceLimit,
+    /// Size of the stack (in bytes).
+    pub stack_size: ResourceLimit,
+    /// Size of the core dump files (in bytes).
+    pub core_file_size: ResourceLimit,
+    /// Resident set size (in bytes).
+    pub resident_set: ResourceLimit,
+    /// Number of processes of the user.
+    pub processes: ResourceLimit,
+    /// Number of open file descriptors.
+    pub open_files: ResourceLimit,
+    /// Size of the memory which can be locked (in bytes).
+    pub locked_memory: ResourceLimit,
+    /// Size of the virtual memory (in bytes).
+    pub address_space: ResourceLimit,
+    /// Number of pending signals.
+    pub pending_signals: ResourceLimit,
+}
+
+// Parses the content of a `/proc/[pid]/limits` file. Columns are separated by at least two
+// spaces, names contain single ones: "Max open files            1024                 4096".
+fn parse_limits(data: &str) -> ProcessLimits {
+    let mut limits = ProcessLimits::default();
+    for line in data.lines().skip(1) {
+        let mut columns = line.split("  ").map(|c| c.trim()).filter(|c| !c.is_empty());
+        let (name, soft, hard) = match (columns.next(), columns.next(), columns.next()) {
+            (Some(name), Some(soft), Some(hard)) => (name, soft, hard),
+            _ => continue,
+        };
+        let limit = match name {
+            "Max cpu time" => &mut limits.cpu_time,
+            "Max file size" => &mut limits.file_size,
+            "Max data size" => &mut limits.data_size,
+            "Max stack size" => &mut limits.stack_size,
+            "Max core file size" => &mut limits.core_file_size,
+            "Max resident set" => &mut limits.resident_set,
+            "Max processes" => &mut limits.processes,
+            "Max open files" => &mut limits.open_files,
+            "Max locked memory" => &mut limits.locked_memory,
+            "Max address space" => &mut limits.address_space,
+            "Max pending signals" => &mut limits.pending_signals,
+            _ => continue,
+        };
+        // "unlimited" isn't a number.
+        limit.soft = u64::from_str(soft).ok();
+        limit.hard = u64::from_str(hard).ok();
+    }
+    limits
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// I/O counters of a process since its start, as listed in `/proc/[pid]/io`. Take a look at
+/// [`Process::get_io`].
+///
+/// [`Process::get_io`]: struct.Process.html#method.get_io
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProcessIo {
+    /// Bytes read from the storage (in bytes).
+    pub read_bytes: u64,
+    /// Bytes written to the storage (in bytes). It includes the writes which were cancelled
+    /// afterwards.
+    pub written_bytes: u64,
+    /// Bytes which were written but never reached the storage, because the file was truncated
+    /// or deleted before being flushed (in bytes).
+    pub cancelled_write_bytes: u64,
+}
+
+impl ProcessIo {
+    /// Returns the bytes which actually reached the storage: [`written_bytes`] minus
+    /// [`cancelled_write_bytes`].
+    ///
+    /// [`written_bytes`]: #structfield.written_bytes
+    /// [`cancelled_write_bytes`]: #structfield.cancelled_write_bytes
+    pub fn get_effective_written_bytes(&self) -> u64 {
+        self.written_bytes.saturating_sub(self.cancelled_write_bytes)
+    }
+}
+
+// Parses the content of a `/proc/[pid]/io` file. Lines look like "read_bytes: 4096".
+fn parse_io(data: &str) -> ProcessIo {
+    let mut io = ProcessIo::default();
+    for line in data.lines() {
+        let mut parts = line.splitn(2, ':');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, u64::from_str(value.trim()).unwrap_or(0)),
+            _ => continue,
+        };
+        match key {
+            "read_bytes" => io.read_bytes = value,
+            "write_bytes" => io.written_bytes = value,
+            "cancelled_write_bytes" => io.cancelled_write_bytes = value,
+            _ => {}
+        }
+    }
+    io
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Memory region of a process, as listed in `/proc/[pid]/maps`. Take a look at
+/// [`Process::get_memory_maps`].
+///
+/// [`Process::get_memory_maps`]: struct.Process.html#method.get_memory_maps
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryMapRegion {
+    /// Address of the start of the region.
+    pub start: u64,
+    /// Address of the end of the region (excluded).
+    pub end: u64,
+    /// Permissions of the region, like "r-xp": read, write, execute and private (`p`) or
+    /// shared (`s`).
+    pub permissions: String,
+    /// Offset of the region in the mapped file.
+    pub offset: u64,
+    /// Mapped file, or pseudo-path like "[heap]", "[stack]" or "[vvar]". `None` for anonymous
+    /// regions.
+    pub pathname: Option<String>,
+}
+
+// Parses the content of a `/proc/[pid]/maps` file. Lines look like
+// "7f3a1c000000-7f3a1c021000 r-xp 00000000 08:01 1234   /usr/lib/libc.so.6", the path being
+// absent for anonymous regions and possibly containing spaces.
+fn parse_maps(data: &str) -> Vec<MemoryMapRegion> {
+    let mut ret = Vec::new();
+    for line in data.lines() {
+        let mut parts = line.splitn(6, ' ');
+        let (range, permissions, offset) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(range), Some(permissions), Some(offset)) => (range, permissions, offset),
+            _ => continue,
+        };
+        let mut range = range.splitn(2, '-');
+        let (start, end) = match (range.next(), range.next()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => continue,
+        };
+        if let (Ok(start), Ok(end), Ok(offset)) = (u64::from_str_radix(start, 16),
+                                                   u64::from_str_radix(end, 16),
+                                                   u64::from_str_radix(offset, 16)) {
+            // Skips the device and the inode.
+            let pathname = parts.nth(2).map(|p| p.trim()).filter(|p| !p.is_empty());
+            ret.push(MemoryMapRegion {
+                start: start,
+                end: end,
+                permissions: permissions.to_owned(),
+                offset: offset,
+                pathname: pathname.map(|p| p.to_owned()),
+            });
+        }
+    }
+    ret
+}
+
 /// Struct containing a process' information.
 #[derive(Clone)]
 pub struct Process {
@@ -100,17 +353,19 @@ pub struct Process {
     pub name: String,
     /// Command line, split into arguments.
     pub cmd: Vec<String>,
-    /// Path to the executable.
+    /// Path to the executable, as the raw target of the `/proc/[pid]/exe` link. If the process
+    /// runs in another mount namespace (a container for example), this path isn't resolved in
+    /// the current one: use [`get_path_in_mount_namespace`] to access it.
+    ///
+    /// [`get_path_in_mount_namespace`]: #method.get_path_in_mount_namespace
     pub exe: String,
     /// Pid of the process.
     pub pid: pid_t,
     /// Pid of the parent process.
     pub parent: Option<pid_t>,
-    /// Environment of the process.
-    pub environ: Vec<String>,
-    /// Current working directory.
+    /// Current working directory, as the raw target of the `/proc/[pid]/cwd` link.
     pub cwd: String,
-    /// Path of the root directory.
+    /// Path of the root directory, as the raw target of the `/proc/[pid]/root` link.
     pub root: String,
     /// Memory usage (in kB).
     pub memory: u64,
@@ -121,6 +376,15 @@ pub struct Process {
     /// Time of process launch (in seconds).
     pub start_time: u64,
     updated: bool,
+    first_sample: bool,
+    // Total CPU time of the system (from `/proc/stat`) when the CPU usage was last computed.
+    cpu_total_time: u64,
+    // Settings of the `System` during the last refresh, used by `Process::refresh`.
+    counter_mode: CounterMode,
+    collect_threads: bool,
+    clock_ticks: u64,
+    // Start time in clock ticks since boot, to detect reused pids. 0 if unknown.
+    start_ticks: u64,
     /// Total cpu usage.
     pub cpu_usage: f32,
     /// User id of the process owner.
@@ -130,8 +394,33 @@ pub struct Process {
     /// Status of process (idle, run, zombie, etc). `None` means `sysinfo` doesn't have
     /// enough rights to get this information.
     pub status: Option<ProcessStatus>,
-    /// Tasks run by this process.
+    /// Tasks run by this process. Their CPU usage is computed separately and is already
+    /// accounted for in this process' `cpu_usage`.
+    ///
+    /// On Linux, these are the threads of the process except the main one, never its child
+    /// processes. Threads share the memory of their process so their `memory` is the part of
+    /// the resident set size exceeding the process' one, usually `0`: summing the `memory` of
+    /// the processes of [`SystemExt::get_process_list`] doesn't count threads. It stays empty
+    /// when [`System::set_collect_threads`] is disabled.
+    ///
+    /// [`SystemExt::get_process_list`]: trait.SystemExt.html#tymethod.get_process_list
+    /// [`System::set_collect_threads`]: struct.System.html#method.set_collect_threads
     pub tasks: HashMap<pid_t, Process>,
+    state_description: String,
+    flags: u32,
+    process_group: pid_t,
+    comm: String,
+    stat_data: String,
+    locked_memory: u64,
+    peak_memory: u64,
+    swap: u64,
+    thread_count: u32,
+    children_utime: u64,
+    children_stime: u64,
+    scheduling_policy: SchedulingPolicy,
+    memory_delta: i64,
+    // Read on the first call to `get_environ`.
+    environ: OnceLock<Vec<String>>,
 }
 
 impl ProcessExt for Process {
@@ -141,7 +430,6 @@ impl ProcessExt for Process {
             pid: pid,
             parent: parent,
             cmd: Vec::new(),
-            environ: Vec::new(),
             exe: String::new(),
             cwd: String::new(),
             root: String::new(),
@@ -152,17 +440,485 @@ impl ProcessExt for Process {
             old_utime: 0,
             old_stime: 0,
             updated: true,
+            first_sample: true,
+            cpu_total_time: 0,
+            counter_mode: CounterMode::Delta,
+            collect_threads: true,
+            // Only known once the process was refreshed by a `System`.
+            clock_ticks: 0,
+            start_ticks: 0,
             start_time: start_time,
             uid: 0,
             gid: 0,
             status: None,
             tasks: HashMap::new(),
+            state_description: String::new(),
+            flags: 0,
+            process_group: 0,
+            comm: String::new(),
+            stat_data: String::new(),
+            locked_memory: 0,
+            peak_memory: 0,
+            swap: 0,
+            thread_count: 0,
+            children_utime: 0,
+            children_stime: 0,
+            scheduling_policy: SchedulingPolicy::Other,
+            memory_delta: 0,
+            environ: OnceLock::new(),
         }
     }
 
     fn kill(&self, signal: ::Signal) -> bool {
         unsafe { kill(self.pid, signal as c_int) == 0 }
     }
+
+    fn get_arguments(&self) -> &[String] {
+        let mut args = if self.cmd.is_empty() { &self.cmd[..] } else { &self.cmd[1..] };
+        // The command line ends with a null terminator which gives an empty last element.
+        if let Some((last, rest)) = args.split_last() {
+            if last.is_empty() {
+                args = rest;
+            }
+        }
+        args
+    }
+
+    fn get_memory_bytes(&self) -> u64 {
+        self.memory * 1024
+    }
+
+    /// The environment is read from `/proc/[pid]/environ` on the first call and then kept. It's
+    /// empty if the process doesn't exist anymore (even if its pid was reused since) or if
+    /// `sysinfo` doesn't have enough rights to get it.
+    ///
+    /// Unlike on macOS, there is no `environ` field on Linux.
+    fn get_environ(&self) -> &[String] {
+        self.environ.get_or_init(|| {
+            let path = Path::new("/proc").join(self.pid.to_string());
+            let environ = copy_from_file(&path.join("environ"));
+            // Checked after reading: if the start time still matches, the environment couldn't
+            // have been read from another process using the same pid.
+            let start_ticks = get_all_data(path.join("stat")).ok()
+                .and_then(|data| parse_stat_file(&data).map(|parts| get_start_ticks(&parts)));
+            if start_ticks == Some(self.start_ticks) {
+                environ
+            } else {
+                Vec::new()
+            }
+        })
+    }
+}
+
+impl Process {
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Re-reads this process' information from its `/proc/[pid]` files. `page_size_kb` is the
+    /// page size (in kB) used to compute the memory usage (see [`System::get_page_size_kb`]).
+    /// Its threads are refreshed as well, unless the `System` it comes from doesn't collect
+    /// them (see [`System::set_collect_threads`]).
+    ///
+    /// Like with [`System::refresh_process`], the CPU usage is computed since the previous
+    /// refresh of the process, whichever it was, following the `System`'s [`CounterMode`].
+    ///
+    /// Returns an error if the process' files couldn't be read (if it doesn't exist anymore
+    /// for example) or [`ProcReadError::PidReused`] if its pid now belongs to another process.
+    ///
+    /// [`System::get_page_size_kb`]: struct.System.html#method.get_page_size_kb
+    /// [`System::set_collect_threads`]: struct.System.html#method.set_collect_threads
+    /// [`System::refresh_process`]: struct.System.html#method.refresh_process
+    /// [`CounterMode`]: enum.CounterMode.html
+    /// [`ProcReadError::PidReused`]: enum.ProcReadError.html#variant.PidReused
+    pub fn refresh(&mut self, page_size_kb: u64) -> Result<(), ProcReadError> {
+        let clock_ticks = if self.clock_ticks != 0 { self.clock_ticks } else { get_clock_ticks() };
+        let settings = ProcessRefreshSettings {
+            page_size_kb: page_size_kb,
+            clock_ticks: clock_ticks,
+            // Only used for new processes.
+            name_source: ProcessNameSource::Cmdline,
+            collect_threads: self.collect_threads,
+        };
+        refresh_process_data(self, settings)?;
+        if let Ok(data) = get_all_data("/proc/stat") {
+            let mode = self.counter_mode;
+            compute_refreshed_process_cpu_usage(self, &data, mode, clock_ticks, false);
+        }
+        Ok(())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the full state description as written in the `State:` line of
+    /// `/proc/[pid]/status` (for example "S (sleeping)").
+    pub fn get_state_description(&self) -> String {
+        self.state_description.clone()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of sockets opened by the process, by looking into `/proc/[pid]/fd`.
+    /// Returns `None` if `sysinfo` doesn't have enough rights to get this information or if the
+    /// process doesn't exist anymore.
+    pub fn get_socket_count(&self) -> Option<usize> {
+        get_socket_inodes(self.pid).map(|inodes| inodes.len())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the cgroup of the process, like "/system.slice/docker-<id>.scope", read from
+    /// `/proc/[pid]/cgroup`. The unified (v2) hierarchy is used when available, otherwise the
+    /// path of the first v1 hierarchy is returned. Since it isn't needed by most users, the file
+    /// is only read when calling this method. Returns `None` if the process doesn't exist anymore.
+    pub fn get_cgroup(&self) -> Option<String> {
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("cgroup"))
+            .ok()
+            .and_then(|data| find_main_cgroup_path(&data))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the proportional set size of the process (in kB): each shared page is divided
+    /// among the processes mapping it, so the values of all the processes add up to the memory
+    /// actually used. It is read from `/proc/[pid]/smaps_rollup` (or `/proc/[pid]/smaps` before
+    /// Linux 4.14) when calling this method since it is expensive for the kernel to compute.
+    /// Returns `None` if `sysinfo` doesn't have enough rights to get this information or if the
+    /// process doesn't exist anymore.
+    pub fn get_proportional_memory(&self) -> Option<u64> {
+        self.read_smaps().map(|data| sum_smaps_field(&data, "Pss"))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the amount of anonymous memory of the process backed by transparent huge pages
+    /// (in kB). Like [`get_proportional_memory`], it is read from `/proc/[pid]/smaps_rollup`
+    /// when calling this method and returns `None` if it can't be.
+    ///
+    /// [`get_proportional_memory`]: #method.get_proportional_memory
+    pub fn get_huge_page_memory(&self) -> Option<u64> {
+        self.read_smaps().map(|data| sum_smaps_field(&data, "AnonHugePages"))
+    }
+
+    fn read_smaps(&self) -> Option<String> {
+        let path = Path::new("/proc").join(self.pid.to_string());
+        get_all_data(path.join("smaps_rollup"))
+            .or_else(|_| get_all_data(path.join("smaps")))
+            .ok()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the time (in nanoseconds) the process spent waiting for a CPU in a run queue
+    /// since its start, read from `/proc/[pid]/schedstat` when calling this method. A quickly
+    /// growing value means the process is slowed down by CPU contention. Returns `None` if the
+    /// kernel doesn't provide scheduler statistics or if the process doesn't exist anymore.
+    pub fn get_run_queue_wait(&self) -> Option<u64> {
+        // The file contains "<time on CPU> <time waiting> <timeslices run>".
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("schedstat"))
+            .ok()
+            .and_then(|data| data.split_whitespace().nth(1).and_then(|v| u64::from_str(v).ok()))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the executable of the process has been deleted or replaced since it
+    /// was started (after a package upgrade for example), in which case the kernel appends
+    /// " (deleted)" to the `/proc/[pid]/exe` link target. The link is read again when calling
+    /// this method; if it can't be, the [`exe`] field is checked instead.
+    ///
+    /// [`exe`]: #structfield.exe
+    pub fn is_binary_deleted(&self) -> bool {
+        match read_link(Path::new("/proc").join(self.pid.to_string()).join("exe")) {
+            Ok(exe) => exe.to_string_lossy().ends_with(" (deleted)"),
+            Err(_) => self.exe.ends_with(" (deleted)"),
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the resource limits of the process, read from `/proc/[pid]/limits` when calling
+    /// this method. Returns `None` if the process doesn't exist anymore.
+    pub fn get_limits(&self) -> Option<ProcessLimits> {
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("limits"))
+            .ok()
+            .map(|data| parse_limits(&data))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the storage I/O counters of the process, read from `/proc/[pid]/io` when calling
+    /// this method. Returns `None` if the process doesn't exist anymore or if the file can't be
+    /// read (which requires the same permissions as `ptrace`).
+    pub fn get_io(&self) -> Option<ProcessIo> {
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("io"))
+            .ok()
+            .map(|data| parse_io(&data))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of bytes written by the process which never reached the storage, read
+    /// from `/proc/[pid]/io` when calling this method. Use [`get_io`] to get it along with the
+    /// read and written bytes.
+    ///
+    /// [`get_io`]: #method.get_io
+    pub fn get_cancelled_write_bytes(&self) -> Option<u64> {
+        self.get_io().map(|io| io.cancelled_write_bytes)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the memory regions of the process, read from `/proc/[pid]/maps` when calling this
+    /// method since the list can be long. Returns an empty `Vec` if the process doesn't exist
+    /// anymore or if the file can't be read (which requires the same permissions as `ptrace`).
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// if let Some(process) = s.get_process(1) {
+    ///     for region in process.get_memory_maps() {
+    ///         println!("{:x}-{:x} {} {:?}", region.start, region.end, region.permissions,
+    ///                  region.pathname);
+    ///     }
+    /// }
+    /// ```
+    pub fn get_memory_maps(&self) -> Vec<MemoryMapRegion> {
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("maps"))
+            .map(|data| parse_maps(&data))
+            .unwrap_or_default()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the name of the kernel function the process is sleeping in ("do_select",
+    /// "io_schedule"...), read from `/proc/[pid]/wchan` when calling this method. For a process in
+    /// uninterruptible sleep (the `D` state, [`ProcessStatus::Idle`]), it tells what it's blocked
+    /// on.
+    /// Returns `None` if the process is running, doesn't exist anymore or if the kernel hides
+    /// this information.
+    ///
+    /// [`ProcessStatus::Idle`]: enum.ProcessStatus.html#variant.Idle
+    pub fn get_wait_channel(&self) -> Option<String> {
+        get_all_data(Path::new("/proc").join(self.pid.to_string()).join("wchan"))
+            .ok()
+            .map(|data| data.trim().to_owned())
+            .filter(|wchan| !wchan.is_empty() && wchan != "0")
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the kernel stack of the process, innermost frame first ("do_select+0x5fa/0x7f0"
+    /// for example), read from `/proc/[pid]/stack` when calling this method. Unlike
+    /// [`get_wait_channel`], it shows the whole path of a blocked system call. Reading it
+    /// requires root privileges, so `None` is returned when it's not allowed (or if the process
+    /// doesn't exist anymore).
+    ///
+    /// [`get_wait_channel`]: #method.get_wait_channel
+    pub fn get_kernel_stack(&self) -> Option<Vec<String>> {
+        let data = get_all_data(Path::new("/proc").join(self.pid.to_string()).join("stack")).ok()?;
+        // Lines look like "[<0>] do_select+0x5fa/0x7f0", the address being hidden most of the
+        // time.
+        Some(data.lines()
+                 .map(|line| match line.find("] ") {
+                     Some(pos) if line.starts_with("[<") => line[pos + 2..].to_owned(),
+                     _ => line.to_owned(),
+                 })
+                 .collect())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the inode number identifying the mount namespace of the process, read from the
+    /// `/proc/[pid]/ns/mnt` link when calling this method. Returns `None` if the process doesn't
+    /// exist anymore or if the link can't be read (which requires the same permissions as
+    /// `ptrace`).
+    pub fn get_mount_namespace(&self) -> Option<u64> {
+        read_namespace(&Path::new("/proc").join(self.pid.to_string()).join("ns/mnt"))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the process runs in another mount namespace than the current process,
+    /// in which case its [`exe`], [`cwd`] and [`root`] paths are relative to a filesystem view
+    /// which might not match the current one.
+    ///
+    /// [`exe`]: #structfield.exe
+    /// [`cwd`]: #structfield.cwd
+    /// [`root`]: #structfield.root
+    pub fn is_in_other_mount_namespace(&self) -> bool {
+        match (self.get_mount_namespace(), read_namespace(Path::new("/proc/self/ns/mnt"))) {
+            (Some(ns), Some(self_ns)) => ns != self_ns,
+            _ => false,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns a path through which `path`, as seen by the process (its [`exe`] or [`cwd`] for
+    /// example), can be accessed from the current mount namespace. It goes through the
+    /// `/proc/[pid]/root` link, which the kernel resolves in the mount namespace of the process.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// if let Some(p) = s.get_process(1337) {
+    ///     println!("{:?}", p.get_path_in_mount_namespace(&p.exe));
+    /// }
+    /// ```
+    ///
+    /// [`exe`]: #structfield.exe
+    /// [`cwd`]: #structfield.cwd
+    pub fn get_path_in_mount_namespace<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        let root = Path::new("/proc").join(self.pid.to_string()).join("root");
+        root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns how much the memory usage (in kB) changed since the previous refresh. A positive
+    /// value means the process is growing.
+    pub fn get_memory_delta(&self) -> i64 {
+        self.memory_delta
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the highest resident set size reached by the process since its start (in kB).
+    /// Unlike [`memory`], it doesn't decrease when the process releases memory.
+    ///
+    /// [`memory`]: #structfield.memory
+    pub fn get_peak_memory(&self) -> u64 {
+        self.peak_memory
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the size of the memory locked with `mlock` by the process (in kB).
+    pub fn get_locked_memory(&self) -> u64 {
+        self.locked_memory
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the size of the memory of the process which has been swapped out (in kB).
+    pub fn get_swap(&self) -> u64 {
+        self.swap
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of threads of the process, the main one included. Unlike the length
+    /// of [`tasks`], it is available even when [`System::set_collect_threads`] is disabled.
+    ///
+    /// [`tasks`]: #structfield.tasks
+    /// [`System::set_collect_threads`]: struct.System.html#method.set_collect_threads
+    pub fn get_thread_count(&self) -> u32 {
+        self.thread_count
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the CPU time (user and system, in clock ticks) consumed by the children of this
+    /// process which have been waited for.
+    pub fn get_children_cpu_time(&self) -> u64 {
+        self.children_utime + self.children_stime
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the scheduling policy of the process (the same value `sched_getscheduler`
+    /// returns).
+    pub fn get_scheduling_policy(&self) -> SchedulingPolicy {
+        self.scheduling_policy
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the TCP connections (IPv4 and IPv6) of the process, by matching its sockets
+    /// against `/proc/[pid]/net/tcp` and `/proc/[pid]/net/tcp6`. The list is empty if
+    /// `sysinfo` doesn't have enough rights to get this information.
+    pub fn get_tcp_connections(&self) -> Vec<TcpConnection> {
+        let inodes = match get_socket_inodes(self.pid) {
+            Some(inodes) => inodes,
+            None => return Vec::new(),
+        };
+        if inodes.is_empty() {
+            return Vec::new();
+        }
+        get_tcp_connections(&Path::new("/proc").join(self.pid.to_string()))
+            .into_iter()
+            .filter(|c| inodes.contains(&c.inode))
+            .collect()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the kernel flags of the process (the `PF_*` values, such as `PF_KTHREAD`).
+    pub fn get_flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the id of the process group of the process (which can be given to `kill` as a
+    /// negative pid to send a signal to the whole group).
+    pub fn get_process_group_id(&self) -> pid_t {
+        self.process_group
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the field at `index` of the `/proc/[pid]/stat` file, as read during the last
+    /// refresh. Indexes start at 0, so they are the field numbers of `man 5 proc` minus one
+    /// (`get_stat_field(39)` is `rt_priority` for example). The command name (index 1) is
+    /// returned without its parentheses. Returns `None` if the kernel doesn't provide this field.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// if let Some(p) = s.get_process(1337) {
+    ///     println!("exit code: {:?}", p.get_stat_field(51));
+    /// }
+    /// ```
+    pub fn get_stat_field(&self, index: usize) -> Option<&str> {
+        let field = parse_stat_file(&self.stat_data)?.get(index).cloned()?;
+        // `parse_stat_file` keeps the parenthesis before the command name.
+        if index == 1 {
+            field.get(1..)
+        } else {
+            Some(field)
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of threads of the process in each status, the main thread (whose
+    /// status is the process' one) included. The threads whose status couldn't be read aren't
+    /// counted. A multithreaded server with most of its threads in uninterruptible sleep
+    /// ([`ProcessStatus::Idle`]) is usually stalled on I/O.
+    ///
+    /// [`ProcessStatus::Idle`]: enum.ProcessStatus.html#variant.Idle
+    pub fn get_thread_states(&self) -> HashMap<ProcessStatus, usize> {
+        let mut ret = HashMap::new();
+        let threads = self.tasks.values().filter_map(|t| t.status);
+        for status in self.status.into_iter().chain(threads) {
+            *ret.entry(status).or_insert(0) += 1;
+        }
+        ret
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the command name stored by the kernel (truncated to 15 bytes for programs), which is
+    /// what `ps` shows by default. Unlike the command line, it isn't changed by programs
+    /// rewriting their `argv[0]` (like nginx workers) and it isn't empty for kernel threads.
+    /// Threads can have their own.
+    pub fn get_comm(&self) -> &str {
+        &self.comm
+    }
 }
 
 #[allow(unused_must_use)]
@@ -171,10 +927,13 @@ impl Debug for Process {
         writeln!(f, "pid: {}", self.pid);
         writeln!(f, "parent: {:?}", self.parent);
         writeln!(f, "name: {}", self.name);
-        writeln!(f, "environment:");
-        for var in &self.environ {
-            if !var.is_empty() {
-                writeln!(f, "\t{}", var);
+        // The environment isn't read just to be displayed.
+        if let Some(environ) = self.environ.get() {
+            writeln!(f, "environment:");
+            for var in environ {
+                if !var.is_empty() {
+                    writeln!(f, "\t{}", var);
+                }
             }
         }
         writeln!(f, "command:");
@@ -194,9 +953,74 @@ impl Debug for Process {
     }
 }
 
-pub fn compute_cpu_usage(p: &mut Process, nb_processors: u64, total_time: f32) {
+// Returns the inodes of the sockets opened by the given pid.
+pub fn get_socket_inodes(pid: pid_t) -> Option<Vec<u64>> {
+    let dir = match read_dir(Path::new("/proc").join(pid.to_string()).join("fd")) {
+        Ok(dir) => dir,
+        Err(_) => return None,
+    };
+    let mut inodes = Vec::new();
+    for entry in dir.filter_map(|e| e.ok()) {
+        if let Ok(target) = read_link(entry.path()) {
+            // Socket links look like "socket:[12345]".
+            let target = target.to_string_lossy();
+            if target.starts_with("socket:[") && target.ends_with(']') {
+                if let Ok(inode) = u64::from_str(&target[8..target.len() - 1]) {
+                    inodes.push(inode);
+                }
+            }
+        }
+    }
+    Some(inodes)
+}
+
+// `new_total_time` and `old_total_time` are the two last total CPU times of the system. If the
+// usage of the process was computed more recently than `old_total_time` (by a single process
+// refresh), the time elapsed since then is used instead.
+pub fn compute_cpu_usage(p: &mut Process, nb_processors: u64, new_total_time: u64,
+                         old_total_time: u64) {
+    let old_total_time = if p.cpu_total_time > old_total_time {
+        p.cpu_total_time
+    } else {
+        old_total_time
+    };
+    let total_time = ::std::cmp::max(1, new_total_time.saturating_sub(old_total_time)) as f32;
     p.cpu_usage = ((p.utime - p.old_utime + p.stime - p.old_stime) * nb_processors * 100) as f32 / total_time;
+    p.cpu_total_time = new_total_time;
+    p.updated = false;
+    p.first_sample = false;
+}
+
+pub fn compute_cpu_usage_since_start(p: &mut Process, uptime: f32, clock_ticks: u64) {
+    let running_time = uptime - p.start_time as f32;
+    p.cpu_usage = if running_time <= 0. || clock_ticks == 0 {
+        0.
+    } else {
+        ((p.utime + p.stime) as f32 / clock_ticks as f32) * 100. / running_time
+    };
     p.updated = false;
+    p.first_sample = false;
+}
+
+// Settings of the `System` reused by `Process::refresh`.
+pub fn record_counter_mode(p: &mut Process, mode: CounterMode) {
+    p.counter_mode = mode;
+}
+
+pub fn record_collect_threads(p: &mut Process, collect_threads: bool) {
+    p.collect_threads = collect_threads;
+}
+
+pub fn record_clock_ticks(p: &mut Process, clock_ticks: u64) {
+    p.clock_ticks = clock_ticks;
+}
+
+pub fn set_start_ticks(p: &mut Process, start_ticks: u64) {
+    p.start_ticks = start_ticks;
+}
+
+pub fn get_start_ticks_of(p: &Process) -> u64 {
+    p.start_ticks
 }
 
 pub fn set_time(p: &mut Process, utime: u64, stime: u64) {
@@ -207,6 +1031,119 @@ pub fn set_time(p: &mut Process, utime: u64, stime: u64) {
     p.updated = true;
 }
 
+// Updates the fields coming from the `/proc/[pid]/status` file.
+pub fn update_status_fields(p: &mut Process, status_data: &str) {
+    for line in status_data.lines() {
+        let mut parts = line.splitn(2, ':');
+        let (key, value) = match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) => (key, value.trim()),
+            _ => continue,
+        };
+        match key {
+            "State" => p.state_description = value.to_owned(),
+            "VmLck" => p.locked_memory = parse_kb_value(value),
+            "VmHWM" => p.peak_memory = parse_kb_value(value),
+            "VmSwap" => p.swap = parse_kb_value(value),
+            "Threads" => p.thread_count = u32::from_str(value).unwrap_or(0),
+            _ => {}
+        }
+    }
+}
+
+// Returns the inode of a `/proc/[pid]/ns/*` link, whose target looks like "mnt:[4026531840]".
+fn read_namespace(path: &Path) -> Option<u64> {
+    let target = read_link(path).ok()?;
+    let target = target.to_str()?;
+    let start = target.find('[')?;
+    u64::from_str(target.get(start + 1..target.len() - 1)?).ok()
+}
+
+// Sums the values (in kB) of the `key` lines of a `smaps` or `smaps_rollup` file.
+fn sum_smaps_field(data: &str, key: &str) -> u64 {
+    data.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(k), Some(value)) if k == key => Some(parse_kb_value(value.trim())),
+                _ => None,
+            }
+        })
+        .sum()
+}
+
+// Parses values looking like "1024 kB".
+fn parse_kb_value(value: &str) -> u64 {
+    value.split_whitespace().next().and_then(|v| u64::from_str(v).ok()).unwrap_or(0)
+}
+
+pub fn set_children_time(p: &mut Process, cutime: u64, cstime: u64) {
+    p.children_utime = cutime;
+    p.children_stime = cstime;
+}
+
+pub fn set_scheduling_policy(p: &mut Process, policy: u32) {
+    p.scheduling_policy = SchedulingPolicy::from(policy);
+}
+
+pub fn set_memory_delta(p: &mut Process, delta: i64) {
+    p.memory_delta = delta;
+}
+
+pub fn set_flags(p: &mut Process, flags: u32) {
+    p.flags = flags;
+}
+
+pub fn set_process_group(p: &mut Process, process_group: pid_t) {
+    p.process_group = process_group;
+}
+
+pub fn set_stat_data(p: &mut Process, data: &str) {
+    p.stat_data.clear();
+    p.stat_data.push_str(data.trim_end());
+}
+
+pub fn set_comm(p: &mut Process, comm: &str) {
+    if p.comm != comm {
+        p.comm = comm.to_owned();
+    }
+}
+
 pub fn has_been_updated(p: &Process) -> bool {
     p.updated
 }
+
+// Returns `true` if the CPU usage of the process has never been computed.
+pub fn is_first_sample(p: &Process) -> bool {
+    p.first_sample
+}
+
+#[test]
+fn test_parse_limits() {
+    let limits = parse_limits(include_str!("../../tests/fixtures/pid_limits"));
+    assert_eq!(limits.cpu_time, ResourceLimit { soft: None, hard: None });
+    assert_eq!(limits.stack_size, ResourceLimit { soft: Some(8388608), hard: None });
+    assert_eq!(limits.core_file_size, ResourceLimit { soft: Some(0), hard: None });
+    assert_eq!(limits.processes, ResourceLimit { soft: Some(23961), hard: Some(23961) });
+    assert_eq!(limits.open_files, ResourceLimit { soft: Some(20000), hard: Some(20000) });
+    assert_eq!(limits.locked_memory, ResourceLimit { soft: Some(8388608), hard: Some(8388608) });
+    assert_eq!(limits.pending_signals, ResourceLimit { soft: Some(23961), hard: Some(23961) });
+}
+
+#[test]
+fn test_parse_maps() {
+    let regions = parse_maps(include_str!("../../tests/fixtures/pid_maps"));
+    assert_eq!(regions.len(), 7);
+    assert_eq!(regions[1], MemoryMapRegion {
+        start: 0x55d5c8a50000,
+        end: 0x55d5c8a55000,
+        permissions: "r-xp".to_owned(),
+        offset: 0x2000,
+        pathname: Some("/usr/bin/cat".to_owned()),
+    });
+    assert_eq!(regions[2].pathname, Some("[heap]".to_owned()));
+    // Anonymous region.
+    assert_eq!(regions[3].pathname, None);
+    assert_eq!(regions[4].pathname, Some("/home/user/My Libs/libfoo.so".to_owned()));
+    assert_eq!(regions[6].start, 0xffffffffff600000);
+    assert_eq!(regions[6].permissions, "--xp");
+}
@@ -4,7 +4,14 @@
 // Copyright (c) 2015 Guillaume Gomez
 //
 
+use std::fs::read_dir;
+use std::path::Path;
+use std::str::FromStr;
+
 use ::ProcessorExt;
+use super::system::get_all_data;
+
+const THROTTLE_COUNT_FILE: &str = "thermal_throttle/core_throttle_count";
 
 /// Struct containing values to compute a CPU usage.
 #[derive(Clone, Copy)]
@@ -82,14 +89,31 @@ impl CpuValues {
         self.user + self.nice + self.system
     }
 
-    /// Returns total time.
+    /// Returns total time. `guest` and `guest_nice` aren't added since the kernel already
+    /// accounts them in `user` and `nice`.
     pub fn total_time(&self) -> u64 {
-        self.work_time() + self.idle + self.iowait + self.irq + self.softirq
-            + self.steal + self.guest + self.guest_nice
+        self.work_time() + self.idle + self.iowait + self.irq + self.softirq + self.steal
     }
 }
 
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Struct containing the sizes (in bytes) of a processor's caches. A cache which doesn't exist
+/// or whose size isn't provided by the kernel is `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CacheInfo {
+    /// Level 1 data cache.
+    pub l1d: Option<u64>,
+    /// Level 1 instruction cache.
+    pub l1i: Option<u64>,
+    /// Level 2 cache.
+    pub l2: Option<u64>,
+    /// Level 3 cache (usually shared between the cores).
+    pub l3: Option<u64>,
+}
+
 /// Struct containing a processor information.
+#[derive(Clone)]
 pub struct Processor {
     old_values: CpuValues,
     new_values: CpuValues,
@@ -128,21 +152,130 @@ impl Processor {
 
     fn set(&mut self, user: u64, nice: u64, system: u64, idle: u64, iowait: u64,
            irq: u64, softirq: u64, steal: u64, guest: u64, guest_nice: u64) {
-        fn min(a: u64, b: u64) -> f32 {
-            (if a == b { 1 } else if a > b { a - b } else { b - a }) as f32
-        }
-        //if !self.new_values.is_zero() {
-            self.old_values = self.new_values;
-        //}
+        self.old_values = self.new_values;
         self.new_values.set(user, nice, system, idle, iowait, irq, softirq, steal,
             guest, guest_nice);
-        self.cpu_usage = min(self.new_values.work_time(), self.old_values.work_time()) /
-            min(self.new_values.total_time(), self.old_values.total_time());
+        // Refreshing twice within the same clock tick gives no usage rather than a full one.
+        self.cpu_usage = compute_usage(&self.new_values, &self.old_values);
         self.old_total_time = self.old_values.total_time();
         self.total_time = self.new_values.total_time();
     }
 }
 
+impl Processor {
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the time (in clock ticks) spent by the processor on user and system tasks since
+    /// the boot, as read during the last refresh.
+    pub fn get_work_time(&self) -> u64 {
+        self.new_values.work_time()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the total time (in clock ticks, idle time included) elapsed for the processor
+    /// since the boot, as read during the last refresh.
+    pub fn get_total_time(&self) -> u64 {
+        self.new_values.total_time()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the usage of the processor between `other`, an older copy of it, and `self`, as a
+    /// fraction between `0` and `1` like [`get_cpu_usage`]. It allows to compute the usage
+    /// between two snapshots taken at any time instead of between the two last refreshes.
+    ///
+    /// ```no_run
+    /// use sysinfo::{ProcessorExt, SystemExt};
+    ///
+    /// let mut s = sysinfo::System::new();
+    /// let before = s.get_processor_list()[0].clone();
+    /// // Do some work...
+    /// s.refresh_system();
+    /// println!("{}%", s.get_processor_list()[0].get_usage_since(&before) * 100.);
+    /// ```
+    ///
+    /// [`get_cpu_usage`]: trait.ProcessorExt.html#tymethod.get_cpu_usage
+    pub fn get_usage_since(&self, other: &Processor) -> f32 {
+        compute_usage(&self.new_values, &other.new_values)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the usage of the processor between the two last refreshes, as a fraction between
+    /// `0` and `1` like [`get_cpu_usage`], leaving the time spent waiting for I/O out of the
+    /// total. Time spent in `iowait` is idle time, so
+    /// a processor mostly waiting for a disk has a low [`get_cpu_usage`] although it has
+    /// nothing else to do: this value tells how busy it is when it isn't waiting.
+    ///
+    /// [`get_cpu_usage`]: trait.ProcessorExt.html#tymethod.get_cpu_usage
+    pub fn get_usage_excluding_iowait(&self) -> f32 {
+        let total = self.new_values.total_time().saturating_sub(self.old_values.total_time());
+        let iowait = self.new_values.iowait.saturating_sub(self.old_values.iowait);
+        let work = self.new_values.work_time().saturating_sub(self.old_values.work_time());
+        if total <= iowait {
+            0.
+        } else {
+            work as f32 / (total - iowait) as f32
+        }
+    }
+
+    // Returns `true` if this processor is the one gathering all the others.
+    fn is_global(&self) -> bool {
+        self.name.len() <= 3
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the processor's frequency is currently capped below its maximum one
+    /// (`scaling_max_freq` lower than `cpuinfo_max_freq`), which is what happens when it is
+    /// thermally throttled. For the processor gathering all the others, returns `true` if any of
+    /// them is throttled.
+    ///
+    /// The values are read from `/sys` on each call.
+    pub fn is_throttled(&self) -> bool {
+        if self.is_global() {
+            get_cpu_dirs().iter().any(|name| is_cpu_throttled(name))
+        } else {
+            is_cpu_throttled(&self.name)
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of times the processor's core has been thermally throttled since the
+    /// boot, or `None` if the information isn't available (it is only provided on x86). For the
+    /// processor gathering all the others, returns the sum of their counts.
+    ///
+    /// The values are read from `/sys` on each call.
+    pub fn get_throttle_count(&self) -> Option<u64> {
+        if self.is_global() {
+            get_cpu_dirs().iter()
+                          .filter_map(|name| read_cpu_value(name, THROTTLE_COUNT_FILE))
+                          .fold(None, |acc, count| Some(acc.unwrap_or(0) + count))
+        } else {
+            read_cpu_value(&self.name, THROTTLE_COUNT_FILE)
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the sizes of the processor's caches, read from
+    /// `/sys/devices/system/cpu/cpuN/cache` on each call. For the processor gathering all the
+    /// others, the caches of the first one are returned.
+    pub fn get_cache_sizes(&self) -> CacheInfo {
+        let name = if self.is_global() {
+            match get_cpu_dirs().into_iter().min_by_key(|n| u32::from_str(&n[3..]).unwrap_or(0)) {
+                Some(name) => name,
+                None => return CacheInfo::default(),
+            }
+        } else {
+            self.name.clone()
+        };
+        get_cache_sizes(&name)
+    }
+}
+
 impl ProcessorExt for Processor {
     fn get_cpu_usage(&self) -> f32 {
         self.cpu_usage
@@ -165,6 +298,97 @@ pub fn set_processor(p: &mut Processor, user: u64, nice: u64, system: u64, idle:
         guest, guest_nice)
 }
 
+// Returns the usage (between `0` and `1`) between the `old` and `new` counters. It is `0` if `new`
+// isn't more recent than `old`.
+fn compute_usage(new: &CpuValues, old: &CpuValues) -> f32 {
+    let total = new.total_time().saturating_sub(old.total_time());
+    if total == 0 {
+        0.
+    } else {
+        new.work_time().saturating_sub(old.work_time()) as f32 / total as f32
+    }
+}
+
+pub fn compute_usage_since_boot(p: &mut Processor) {
+    let total_time = p.new_values.total_time();
+    p.cpu_usage = if total_time == 0 {
+        0.
+    } else {
+        p.new_values.work_time() as f32 / total_time as f32
+    };
+}
+
 pub fn get_raw_times(p: &Processor) -> (u64, u64) {
     (p.new_values.total_time(), p.old_values.total_time())
 }
+
+// Returns the names of the processors' directories in `/sys/devices/system/cpu` ("cpu0", ...).
+fn get_cpu_dirs() -> Vec<String> {
+    let mut ret = Vec::new();
+    if let Ok(dir) = read_dir("/sys/devices/system/cpu") {
+        for entry in dir.filter_map(|e| e.ok()) {
+            if let Some(name) = entry.file_name().to_str() {
+                if name.len() > 3 && name.starts_with("cpu") &&
+                   name[3..].chars().all(|c| c.is_ascii_digit()) {
+                    ret.push(name.to_owned());
+                }
+            }
+        }
+    }
+    ret
+}
+
+fn read_cpu_value(name: &str, file: &str) -> Option<u64> {
+    get_all_data(Path::new("/sys/devices/system/cpu").join(name).join(file))
+        .ok()
+        .and_then(|data| u64::from_str(data.trim()).ok())
+}
+
+// Reads the `cache/indexN` directories of the given processor.
+fn get_cache_sizes(name: &str) -> CacheInfo {
+    let mut info = CacheInfo::default();
+    let dir = match read_dir(Path::new("/sys/devices/system/cpu").join(name).join("cache")) {
+        Ok(dir) => dir,
+        Err(_) => return info,
+    };
+    for entry in dir.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let read = |file: &str| get_all_data(path.join(file)).map(|s| s.trim().to_owned());
+        let cache = match (read("level"), read("type")) {
+            (Ok(ref level), Ok(ref kind)) => match (level.as_str(), kind.as_str()) {
+                ("1", "Data") => &mut info.l1d,
+                ("1", "Instruction") => &mut info.l1i,
+                ("2", _) => &mut info.l2,
+                ("3", _) => &mut info.l3,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        *cache = read("size").ok().and_then(|size| parse_cache_size(&size));
+    }
+    info
+}
+
+// Parses sizes like "32K" or "8M".
+fn parse_cache_size(size: &str) -> Option<u64> {
+    let (value, unit) = match size.char_indices().find(|&(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => (&size[..i], &size[i..]),
+        None => (size, ""),
+    };
+    let multiplier = match unit {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    u64::from_str(value).ok().map(|v| v * multiplier)
+}
+
+fn is_cpu_throttled(name: &str) -> bool {
+    match (read_cpu_value(name, "cpufreq/scaling_max_freq"),
+           read_cpu_value(name, "cpufreq/cpuinfo_max_freq")) {
+        (Some(current), Some(max)) => current < max,
+        _ => false,
+    }
+}
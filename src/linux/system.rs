@@ -4,21 +4,98 @@
 // Copyright (c) 2015 Guillaume Gomez
 //
 
+use sys::cgroup;
 use sys::component::{self, Component};
 use sys::processor::*;
 use sys::process::*;
-use sys::Disk;
+use sys::{BlockDevice, Disk, DiskKind, DiskListKind, MountInfo};
 use sys::disk;
-use ::{DiskExt, ProcessExt, SystemExt};
+use sys::numa::{self, NumaNode};
+use sys::network::{self, parse_snmp_file, NetworkData, ProtocolStats};
+use sys::users::UsersCache;
+use sys::virtualization::{self, Virtualization};
+use ::{DiskExt, ProcessExt, ProcessorExt, SystemExt};
 use std::fs::{File, read_link};
-use std::io::{self, Read};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ffi::OsString;
 use std::str::FromStr;
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use libc::{pid_t, uid_t, sysconf, _SC_CLK_TCK, _SC_PAGESIZE};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use libc::{gid_t, pid_t, uid_t, sysconf, ESRCH, _SC_CLK_TCK, _SC_PAGESIZE};
 use utils::realpath;
 
+// Thresholds used by `System::is_under_memory_pressure`.
+const MEMORY_PRESSURE_STALL_THRESHOLD: f32 = 10.;
+const MEMORY_PRESSURE_AVAILABLE_RATIO: f32 = 0.1;
+const MEMORY_PRESSURE_SWAP_OUT_RATE: f32 = 256.;
+
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing how the counter-based metrics are reported. It affects the CPU usage of
+/// the processors ([`ProcessorExt::get_cpu_usage`]) and of the processes
+/// ([`Process::cpu_usage`]). The memory, swap and disk information are always absolute values.
+///
+/// [`ProcessorExt::get_cpu_usage`]: trait.ProcessorExt.html#tymethod.get_cpu_usage
+/// [`Process::cpu_usage`]: struct.Process.html#structfield.cpu_usage
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CounterMode {
+    /// Values are computed between the two last refreshes (the default).
+    Delta,
+    /// Values are computed since the boot (for processors) or since the process' start (for
+    /// processes).
+    SinceBoot,
+}
+
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing where the [`name`] of the processes comes from.
+///
+/// [`name`]: struct.Process.html#structfield.name
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ProcessNameSource {
+    /// The file name of the first argument of the command line (the default).
+    Cmdline,
+    /// The command name stored by the kernel, like `ps` shows it (see [`Process::get_comm`]).
+    ///
+    /// [`Process::get_comm`]: struct.Process.html#method.get_comm
+    Comm,
+}
+
+/// **WARNING**: This struct is specific to Linux.
+///
+/// Cost of a refresh, returned by [`System::refresh_with_stats`].
+///
+/// [`System::refresh_with_stats`]: struct.System.html#method.refresh_with_stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RefreshStats {
+    /// Time the refresh took.
+    pub duration: Duration,
+    /// Number of processes and threads whose `/proc/[pid]` entry was read.
+    pub processes_scanned: usize,
+    /// Number of files read.
+    pub files_read: usize,
+}
+
+thread_local! {
+    // Counters used by `System::refresh_with_stats`. They only ever increase: the cost of a
+    // refresh is the difference between their values before and after it.
+    static FILES_READ: Cell<usize> = const { Cell::new(0) };
+    static PROCESSES_SCANNED: Cell<usize> = const { Cell::new(0) };
+}
+
+pub fn count_file_read() {
+    FILES_READ.with(|c| c.set(c.get().wrapping_add(1)));
+}
+
+fn count_process_scanned() {
+    PROCESSES_SCANNED.with(|c| c.set(c.get().wrapping_add(1)));
+}
+
 /// Structs containing system's information.
 #[derive(Debug)]
 pub struct System {
@@ -27,44 +104,943 @@ pub struct System {
     mem_free: u64,
     swap_total: u64,
     swap_free: u64,
+    mem_locked: u64,
+    mem_free_estimated: bool,
     processors: Vec<Processor>,
     page_size_kb: u64,
+    clock_ticks: u64,
     temperatures: Vec<Component>,
     disks: Vec<Disk>,
+    disk_list_kind: DiskListKind,
+    max_disk_failures: Option<u32>,
+    networks: HashMap<String, NetworkData>,
+    networks_read_at: Option<Instant>,
+    counter_mode: CounterMode,
+    process_name_source: ProcessNameSource,
+    estimate_new_processes_cpu: bool,
+    collect_threads: bool,
+    users: UsersCache,
+    meminfo_file: Option<File>,
+    stat_file: Option<File>,
+    vmstat_file: Option<File>,
+    vmstat: HashMap<String, u64>,
+    swap_out_rate: f32,
+    interrupts: Vec<u64>,
+    old_interrupts_total: u64,
+    softirqs_total: u64,
+    old_softirqs_total: u64,
+    system_refreshed_at: Instant,
+    processes_refreshed_at: Instant,
+    disks_refreshed_at: Instant,
 }
 
 impl System {
     fn clear_procs(&mut self) {
         if !self.processors.is_empty() {
             let (new, old) = get_raw_times(&self.processors[0]);
-            let total_time = (if old > new { 1 } else { new - old }) as f32;
-            let mut to_delete = Vec::new();
             let nb_processors = self.processors.len() as u64 - 1;
+            let uptime = if self.counter_mode == CounterMode::SinceBoot ||
+                            self.estimate_new_processes_cpu {
+                get_uptime()
+            } else {
+                0.
+            };
+            clear_tasks(&mut self.process_list, self.counter_mode, nb_processors, (new, old),
+                        uptime, self.clock_ticks, self.estimate_new_processes_cpu);
+        }
+    }
+
+    fn get_process_refresh_settings(&self) -> ProcessRefreshSettings {
+        ProcessRefreshSettings {
+            page_size_kb: self.page_size_kb,
+            clock_ticks: self.clock_ticks,
+            name_source: self.process_name_source,
+            collect_threads: self.collect_threads,
+        }
+    }
+
+    // Computes the CPU usage of processes refreshed on their own. The total CPU time of the
+    // system is read again without updating the processors, so their usage isn't affected.
+    fn compute_processes_cpu_usage(&mut self, pids: &[pid_t]) {
+        let data = match get_all_data_reusing_file(&mut self.stat_file, "/proc/stat") {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        for pid in pids {
+            if let Some(p) = self.process_list.tasks.get_mut(pid) {
+                compute_refreshed_process_cpu_usage(p, &data, self.counter_mode, self.clock_ticks,
+                                                    self.estimate_new_processes_cpu);
+            }
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Refresh *only* the process corresponding to `pid`.
+    /// Fails if this process is not yet in the process list or if `pid` is negative. If the
+    /// process doesn't exist anymore, it's removed from the list.
+    /// If the pid now belongs to another process (its start time changed), the old entry is
+    /// replaced by a new one instead of being updated.
+    ///
+    /// The CPU usage of the process is computed since its previous refresh, whichever it was.
+    /// The total CPU time of the system is read again for this, without changing the usage of
+    /// the processors.
+    ///
+    /// To keep working on the process afterwards without looking it up again, use
+    /// [`get_process_mut`] and [`Process::refresh`] instead.
+    ///
+    /// [`get_process_mut`]: #method.get_process_mut
+    /// [`Process::refresh`]: struct.Process.html#method.refresh
+    pub fn refresh_process(&mut self, pid: pid_t) -> bool {
+        if pid < 0 {
+            return false;
+        }
+        let settings = self.get_process_refresh_settings();
+        let res = match self.process_list.tasks.get_mut(&pid) {
+            Some(proc_) => refresh_process_data(proc_, settings),
+            None => return false,
+        };
+        match res {
+            Ok(()) => {
+                self.compute_processes_cpu_usage(&[pid]);
+                true
+            }
+            Err(ProcReadError::PidReused) => {
+                // The old process is gone, the new one replaces it.
+                self.process_list.tasks.remove(&pid);
+                _get_process_data(&Path::new("/proc").join(pid.to_string()),
+                                  &mut self.process_list, 0, settings);
+                self.process_list.tasks.contains_key(&pid)
+            }
+            Err(ProcReadError::NotFound) => {
+                self.process_list.tasks.remove(&pid);
+                false
+            }
+            // The process is still there, its information just couldn't be read this time.
+            Err(_) => false,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Walks through the processes of the given `/proc` directory and calls `f` with a summary
+    /// of each of them. Nothing is kept once `f` returned, making it a cheaper alternative to
+    /// [`refresh_processes`] for tools which only need to scan the processes once. The page
+    /// size and the clock ticks of this `System` are used to compute the memory and start time.
+    ///
+    /// ```no_run
+    /// use std::path::Path;
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// s.for_each_process(Path::new("/proc"), |p| {
+    ///     if p.comm == "bash" {
+    ///         println!("{}", p.pid);
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [`refresh_processes`]: trait.SystemExt.html#tymethod.refresh_processes
+    pub fn for_each_process<F: FnMut(&ProcessStat)>(&self, root: &Path, mut f: F) {
+        let dir = match fs::read_dir(root) {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        for entry in dir.filter_map(|e| e.ok()) {
+            let pid = match entry.file_name().to_str().map(pid_t::from_str) {
+                Some(Ok(pid)) => pid,
+                _ => continue,
+            };
+            let data = match read_proc_file(entry.path().join("stat")) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            let parts = match parse_stat_file(&data) {
+                Some(parts) => parts,
+                None => continue,
+            };
+            f(&ProcessStat {
+                pid: pid,
+                parent: match pid_t::from_str(parts[3]) {
+                    Ok(0) | Err(_) => None,
+                    Ok(p) => Some(p),
+                },
+                comm: parts[1].splitn(2, '(').nth(1).unwrap_or("").to_owned(),
+                status: parts[2].chars().next().map(ProcessStatus::from),
+                utime: u64::from_str(parts[13]).unwrap_or(0),
+                stime: u64::from_str(parts[14]).unwrap_or(0),
+                memory: u64::from_str(parts[23]).unwrap_or(0) * self.page_size_kb,
+                start_time: get_start_ticks(&parts) / self.clock_ticks,
+            });
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Refresh *only* the processes corresponding to the given `pids`: unknown ones are added
+    /// to the process list, known ones are updated and the ones which don't exist anymore are
+    /// removed. Unlike [`refresh_processes`], it doesn't go through the whole `/proc` directory.
+    ///
+    /// Like with [`refresh_process`], the CPU usage of the processes is computed since their
+    /// previous refresh, whichever it was.
+    ///
+    /// [`refresh_processes`]: trait.SystemExt.html#tymethod.refresh_processes
+    /// [`refresh_process`]: #method.refresh_process
+    pub fn refresh_process_list_for(&mut self, pids: &[pid_t]) {
+        let mut refreshed = Vec::with_capacity(pids.len());
+        for pid in pids {
+            let path = Path::new("/proc").join(pid.to_string());
+            if path.exists() {
+                let settings = self.get_process_refresh_settings();
+                _get_process_data(&path, &mut self.process_list, 0, settings);
+                refreshed.push(*pid);
+            } else {
+                self.process_list.tasks.remove(pid);
+            }
+        }
+        self.compute_processes_cpu_usage(&refreshed);
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the processes belonging to the process group `pgid`.
+    pub fn get_process_group(&self, pgid: pid_t) -> Vec<&Process> {
+        self.process_list.tasks.values().filter(|p| p.get_process_group_id() == pgid).collect()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the name of the user with the given uid, if a process owned by this user was seen
+    /// by the last call to [`refresh_processes`]. The names are resolved once per uid and kept
+    /// until `/etc/passwd` is modified, so refreshing doesn't query the users database for every
+    /// process.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// for p in s.get_process_list().values() {
+    ///     println!("{} => {:?}", p.pid, s.get_user_name(p.uid));
+    /// }
+    /// ```
+    ///
+    /// [`refresh_processes`]: trait.SystemExt.html#tymethod.refresh_processes
+    pub fn get_user_name(&self, uid: uid_t) -> Option<&str> {
+        self.users.get(uid)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the processes which have some memory swapped out, the ones using the most swap
+    /// first. Take a look at [`Process::get_swap`].
+    ///
+    /// [`Process::get_swap`]: struct.Process.html#method.get_swap
+    pub fn get_swapping_processes(&self) -> Vec<&Process> {
+        let mut ret: Vec<&Process> = self.process_list.tasks.values()
+                                                           .filter(|p| p.get_swap() > 0)
+                                                           .collect();
+        ret.sort_by_key(|p| ::std::cmp::Reverse(p.get_swap()));
+        ret
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of processes in each status. The processes whose status couldn't be
+    /// read aren't counted and the threads aren't included.
+    pub fn get_process_count_by_status(&self) -> HashMap<ProcessStatus, usize> {
+        let mut ret = HashMap::new();
+        for status in self.process_list.tasks.values().filter_map(|p| p.status) {
+            *ret.entry(status).or_insert(0) += 1;
+        }
+        ret
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns a mutable reference to the process corresponding to the given pid or `None` if
+    /// no such process exists. Combined with [`Process::refresh`], it allows to update a known
+    /// process without going through the whole process list.
+    ///
+    /// [`Process::refresh`]: struct.Process.html#method.refresh
+    pub fn get_process_mut(&mut self, pid: pid_t) -> Option<&mut Process> {
+        self.process_list.tasks.get_mut(&pid)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the page size (in kB) used to compute processes' memory usage.
+    pub fn get_page_size_kb(&self) -> u64 {
+        self.page_size_kb
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// The disk list will be emptied then recomputed using the given `kind` of mounts. The
+    /// following calls to [`refresh_disk_list`] will keep using it.
+    ///
+    /// [`refresh_disk_list`]: trait.SystemExt.html#tymethod.refresh_disk_list
+    pub fn refresh_disk_list_with(&mut self, kind: DiskListKind) {
+        self.disk_list_kind = kind;
+        self.disks = get_all_disks(kind);
+        self.disks_refreshed_at = Instant::now();
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Calls `refresh` and returns how long it took, how many processes it went through and
+    /// how many files it read. It allows to measure the cost of any refresh method:
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let mut s = System::new();
+    /// let stats = s.refresh_with_stats(|s| s.refresh_processes());
+    /// println!("{:?} to read {} files", stats.duration, stats.files_read);
+    /// ```
+    pub fn refresh_with_stats<F: FnOnce(&mut System)>(&mut self, refresh: F) -> RefreshStats {
+        let files_read = FILES_READ.with(|c| c.get());
+        let processes_scanned = PROCESSES_SCANNED.with(|c| c.get());
+        let start = Instant::now();
+        refresh(self);
+        RefreshStats {
+            duration: start.elapsed(),
+            processes_scanned: PROCESSES_SCANNED.with(|c| c.get()).wrapping_sub(processes_scanned),
+            files_read: FILES_READ.with(|c| c.get()).wrapping_sub(files_read),
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns when the information was last refreshed, whatever the refreshed part was. It
+    /// allows to check if the data is stale:
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use sysinfo::SystemExt;
+    ///
+    /// let mut s = sysinfo::System::new();
+    /// if s.get_last_refresh().elapsed() > Duration::from_secs(5) {
+    ///     s.refresh_all();
+    /// }
+    /// ```
+    pub fn get_last_refresh(&self) -> Instant {
+        ::std::cmp::max(self.system_refreshed_at,
+                        ::std::cmp::max(self.processes_refreshed_at, self.disks_refreshed_at))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns when [`refresh_system`] was last called.
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    pub fn get_last_system_refresh(&self) -> Instant {
+        self.system_refreshed_at
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns when [`refresh_processes`] was last called. Refreshing only some processes
+    /// doesn't update it.
+    ///
+    /// [`refresh_processes`]: trait.SystemExt.html#tymethod.refresh_processes
+    pub fn get_last_processes_refresh(&self) -> Instant {
+        self.processes_refreshed_at
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns when the disks were last refreshed, either with [`refresh_disks`] or by
+    /// recomputing the disk list.
+    ///
+    /// [`refresh_disks`]: trait.SystemExt.html#tymethod.refresh_disks
+    pub fn get_last_disks_refresh(&self) -> Instant {
+        self.disks_refreshed_at
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Sets after how many failed updates in a row a disk is removed by [`refresh_disks`]. An
+    /// update fails when the disk information can't be read or when it isn't in the mount table
+    /// anymore. With `None` (the default), disks are never removed and keep their last values.
+    ///
+    /// [`refresh_disks`]: trait.SystemExt.html#tymethod.refresh_disks
+    pub fn set_max_disk_failures(&mut self, max_failures: Option<u32>) {
+        self.max_disk_failures = max_failures;
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns whether the system runs in a container (checked first, using `/.dockerenv`,
+    /// `/run/.containerenv` and `/proc/1/cgroup`), in a virtual machine (using the DMI
+    /// information and the `hypervisor` CPU flag) or on bare-metal. It is computed on each call.
+    pub fn get_virtualization(&self) -> Virtualization {
+        virtualization::get_virtualization()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Refreshes the processors' usage (and the interrupt counters, which come from the same
+    /// `/proc/stat` file) without reading the memory information or the components' sensors like
+    /// [`refresh_system`] does. It is meant for frequent CPU usage polling.
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    pub fn refresh_cpu(&mut self) {
+        let data = get_all_data_reusing_file(&mut self.stat_file, "/proc/stat")
+            .unwrap_or_default();
+        let first = self.processors.is_empty();
+        for (i, line) in parse_cpu_lines(&data).into_iter().enumerate() {
+            let v = line.values;
+            if first {
+                self.processors.push(new_processor(line.name, v[0], v[1], v[2], v[3], v[4], v[5],
+                                                   v[6], v[7], v[8], v[9]));
+            } else if let Some(processor) = self.processors.get_mut(i) {
+                set_processor(processor, v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8],
+                              v[9]);
+            }
+        }
+        let interrupts = parse_stat_counters(&data, "intr");
+        let softirqs_total = parse_stat_counters(&data, "softirq").first().cloned().unwrap_or(0);
+        // There is nothing to compare the first values with, so the first deltas are 0.
+        self.old_interrupts_total = if first {
+            interrupts.first().cloned().unwrap_or(0)
+        } else {
+            self.interrupts.first().cloned().unwrap_or(0)
+        };
+        self.old_softirqs_total = if first { softirqs_total } else { self.softirqs_total };
+        self.interrupts = interrupts;
+        self.softirqs_total = softirqs_total;
+        if self.counter_mode == CounterMode::SinceBoot {
+            for processor in &mut self.processors {
+                compute_usage_since_boot(processor);
+            }
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Refreshes the traffic of the network interfaces from `/proc/net/dev`. It isn't done by
+    /// [`refresh_system`], so that pollers which don't need it don't pay for it. The traffic and
+    /// the rates returned by [`get_networks`] are computed between the two last calls (the
+    /// first one being done when the `System` is created).
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    /// [`get_networks`]: #method.get_networks
+    pub fn refresh_networks(&mut self) {
+        let now = Instant::now();
+        let elapsed_secs = self.networks_read_at.map(|read_at| {
+            let elapsed = now.duration_since(read_at);
+            elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1e9
+        }).unwrap_or(0.);
+        let counters = get_all_data("/proc/net/dev").map(|data| network::parse_net_dev(&data))
+                                                    .unwrap_or_default();
+        network::update_networks(&mut self.networks, counters, elapsed_secs);
+        self.networks_read_at = Some(now);
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the network interfaces of the network namespace of the current process, with
+    /// their traffic between the two last calls to [`refresh_networks`].
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let mut s = System::new();
+    /// // Wait a bit so the traffic covers some time...
+    /// s.refresh_networks();
+    /// for (name, network) in s.get_networks() {
+    ///     println!("{}: {} B/s in, {} B/s out", name, network.get_received_rate(),
+    ///              network.get_transmitted_rate());
+    /// }
+    /// ```
+    ///
+    /// [`refresh_networks`]: #method.refresh_networks
+    pub fn get_networks(&self) -> &HashMap<String, NetworkData> {
+        &self.networks
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the IP, ICMP, TCP and UDP counters of the network namespace of the current
+    /// process, read from `/proc/net/snmp` when calling this method. Unlike the interfaces' byte
+    /// counts, they show protocol-level problems such as retransmitted TCP segments.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// let stats = s.get_protocol_stats();
+    /// println!("retransmitted segments: {:?}", stats.tcp.get("RetransSegs"));
+    /// ```
+    pub fn get_protocol_stats(&self) -> ProtocolStats {
+        get_all_data("/proc/net/snmp").map(|data| parse_snmp_file(&data)).unwrap_or_default()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the name of the process with pid 1 ("systemd", "openrc-init", "runit"...), which
+    /// is the init system on most machines. It is read from `/proc/1/comm` when calling this
+    /// method since the command line is often just "/sbin/init"; the name of the process in the
+    /// process list is used if the file can't be read. Inside a container, it can be any
+    /// program.
+    pub fn get_init_system(&self) -> Option<String> {
+        match get_all_data("/proc/1/comm") {
+            Ok(ref comm) if !comm.trim().is_empty() => Some(comm.trim().to_owned()),
+            _ => self.get_process(1).map(|p| p.name.clone()).filter(|name| !name.is_empty()),
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the vendor of the first processor ("GenuineIntel" or "AuthenticAMD" for example),
+    /// read from `/proc/cpuinfo` when calling this method. On ARM, the "CPU implementer" code is
+    /// returned instead.
+    pub fn get_cpu_vendor(&self) -> Option<String> {
+        let data = get_all_data("/proc/cpuinfo").ok()?;
+        parse_cpuinfo_field(&data, "vendor_id")
+            .or_else(|| parse_cpuinfo_field(&data, "CPU implementer"))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the model name of the first processor, read from `/proc/cpuinfo` when calling this
+    /// method. Most machines only have one processor model, so it describes all of them.
+    pub fn get_cpu_brand(&self) -> Option<String> {
+        let data = get_all_data("/proc/cpuinfo").ok()?;
+        parse_cpuinfo_field(&data, "model name")
+            .or_else(|| parse_cpuinfo_field(&data, "Processor"))
+            .or_else(|| parse_cpuinfo_field(&data, "cpu model"))
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns every entry of the mount table, including the pseudo and virtual file systems
+    /// which aren't listed by [`get_disks`]. The table is read on each call.
+    ///
+    /// [`get_disks`]: trait.SystemExt.html#tymethod.get_disks
+    pub fn get_mounts(&self) -> Vec<MountInfo> {
+        get_all_data("/proc/mounts").map(|data| disk::parse_mounts(&data)).unwrap_or_default()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns every block device of `/sys/block`, each one followed by its partitions. Unlike
+    /// [`get_disks`], which only lists mounted file systems, it includes unmounted and
+    /// unformatted devices. The list is read on each call.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// for device in s.get_block_devices().iter().filter(|d| !d.mounted) {
+    ///     println!("{}: {} B", device.name, device.size);
+    /// }
+    /// ```
+    ///
+    /// [`get_disks`]: trait.SystemExt.html#tymethod.get_disks
+    pub fn get_block_devices(&self) -> Vec<BlockDevice> {
+        disk::get_block_devices(&self.get_mounts())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the CPU time (in clock ticks, summed over all the processors) elapsed between the
+    /// two last refreshes, or since boot when using [`CounterMode::SinceBoot`].
+    ///
+    /// [`CounterMode::SinceBoot`]: enum.CounterMode.html#variant.SinceBoot
+    pub fn get_total_cpu_time(&self) -> u64 {
+        match self.processors.first() {
+            Some(p) => {
+                let (new, old) = get_raw_times(p);
+                if self.counter_mode == CounterMode::SinceBoot {
+                    new
+                } else {
+                    new.saturating_sub(old)
+                }
+            }
+            None => 0,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the usage of all the processors, as a fraction between `0` and `1` whatever the
+    /// number of processors is, like [`ProcessorExt::get_cpu_usage`].
+    ///
+    /// Computing it requires two samples of `/proc/stat`: the first one is taken when the
+    /// `System` is created, so this returns `0.0` until the next call to [`refresh_system`],
+    /// which then gives the usage between both samples. With [`CounterMode::SinceBoot`], the
+    /// usage since the boot is returned right away instead.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let mut s = System::new();
+    /// assert_eq!(s.get_total_cpu_usage(), 0.);
+    /// // Wait a bit so the second sample covers some time...
+    /// s.refresh_system();
+    /// println!("{}%", s.get_total_cpu_usage() * 100.);
+    /// ```
+    ///
+    /// [`ProcessorExt::get_cpu_usage`]: trait.ProcessorExt.html#tymethod.get_cpu_usage
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    /// [`CounterMode::SinceBoot`]: enum.CounterMode.html#variant.SinceBoot
+    pub fn get_total_cpu_usage(&self) -> f32 {
+        self.processors.first().map(|p| p.get_cpu_usage()).unwrap_or(0.)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the CPU usage of the process corresponding to `pid` added to the one of all its
+    /// descendants (its children, their children and so on), with the same unit as
+    /// [`ProcessExt::get_cpu_usage`]. Threads aren't added again since a process' usage already
+    /// accounts for them. Returns `0` if there is no process with this pid.
+    ///
+    /// [`ProcessExt::get_cpu_usage`]: trait.ProcessExt.html#tymethod.get_cpu_usage
+    pub fn get_subtree_cpu_usage(&self, pid: pid_t) -> f32 {
+        self.get_descendants(pid).iter().map(|p| p.cpu_usage).sum()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the memory usage (in kB) of the process corresponding to `pid` added to the one
+    /// of all its descendants (its children, their children and so on). Returns `0` if there is
+    /// no process with this pid.
+    pub fn get_process_family_memory(&self, pid: pid_t) -> u64 {
+        self.get_descendants(pid).iter().map(|p| p.memory).sum()
+    }
+
+    // Returns the process corresponding to `pid` and all its descendants, found by following
+    // the parent pids.
+    fn get_descendants(&self, pid: pid_t) -> Vec<&Process> {
+        let mut ret = Vec::new();
+        if !self.process_list.tasks.contains_key(&pid) {
+            return ret;
+        }
+        let mut children: HashMap<pid_t, Vec<pid_t>> = HashMap::new();
+        for p in self.process_list.tasks.values() {
+            if let Some(parent) = p.parent {
+                children.entry(parent).or_default().push(p.pid);
+            }
+        }
+        let mut to_visit = vec![pid];
+        let mut visited = HashSet::new();
+
+        while let Some(current) = to_visit.pop() {
+            // A reused pid could make a loop appear between two refreshes.
+            if !visited.insert(current) {
+                continue;
+            }
+            if let Some(p) = self.process_list.tasks.get(&current) {
+                ret.push(p);
+            }
+            if let Some(pids) = children.get(&current) {
+                to_visit.extend(pids);
+            }
+        }
+        ret
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of interrupts serviced between the two last refreshes, or since boot
+    /// when using [`CounterMode::SinceBoot`].
+    ///
+    /// [`CounterMode::SinceBoot`]: enum.CounterMode.html#variant.SinceBoot
+    pub fn get_interrupts_total(&self) -> u64 {
+        let total = self.interrupts.first().cloned().unwrap_or(0);
+        if self.counter_mode == CounterMode::SinceBoot {
+            total
+        } else {
+            total.saturating_sub(self.old_interrupts_total)
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of interrupts serviced since boot for each IRQ number, as listed in
+    /// the `intr` line of `/proc/stat`: the first value is for IRQ 0, the second for IRQ 1, etc.
+    pub fn get_interrupt_counts(&self) -> &[u64] {
+        if self.interrupts.is_empty() {
+            &self.interrupts
+        } else {
+            &self.interrupts[1..]
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of softirqs serviced between the two last refreshes, or since boot
+    /// when using [`CounterMode::SinceBoot`].
+    ///
+    /// [`CounterMode::SinceBoot`]: enum.CounterMode.html#variant.SinceBoot
+    pub fn get_softirqs_total(&self) -> u64 {
+        if self.counter_mode == CounterMode::SinceBoot {
+            self.softirqs_total
+        } else {
+            self.softirqs_total.saturating_sub(self.old_softirqs_total)
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the value of the given `/proc/vmstat` counter (like "pgfault") as read during the
+    /// last [`refresh_system`], or `None` if the kernel doesn't provide it. The counters are
+    /// cumulative since boot.
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    pub fn get_vmstat_field(&self, key: &str) -> Option<u64> {
+        self.vmstat.get(key).cloned()
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of processes killed by the OOM killer since boot (available since
+    /// Linux 4.13).
+    pub fn get_oom_kill_count(&self) -> Option<u64> {
+        self.get_vmstat_field("oom_kill")
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of major page faults (the ones which required a disk access) since
+    /// boot.
+    pub fn get_major_page_faults(&self) -> Option<u64> {
+        self.get_vmstat_field("pgmajfault")
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of pages swapped in and swapped out since boot.
+    pub fn get_swapped_pages(&self) -> Option<(u64, u64)> {
+        match (self.get_vmstat_field("pswpin"), self.get_vmstat_field("pswpout")) {
+            (Some(swapped_in), Some(swapped_out)) => Some((swapped_in, swapped_out)),
+            _ => None,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of pages swapped out per second between the two last calls to
+    /// [`refresh_system`].
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    pub fn get_swap_out_rate(&self) -> f32 {
+        self.swap_out_rate
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the system looks short of memory, which is the case if any of these is
+    /// true:
+    ///
+    /// * Some tasks were stalled waiting for memory more than 10% of the time over the last 10
+    ///   seconds, according to `/proc/pressure/memory` (available since Linux 4.20, read on each
+    ///   call). This is skipped when the file isn't available.
+    /// * The available memory is less than 10% of the total memory.
+    /// * More than 256 pages per second were swapped out between the two last calls to
+    ///   [`refresh_system`] (see [`get_swap_out_rate`]).
+    ///
+    /// [`refresh_system`]: trait.SystemExt.html#tymethod.refresh_system
+    /// [`get_swap_out_rate`]: #method.get_swap_out_rate
+    pub fn is_under_memory_pressure(&self) -> bool {
+        let stalled = get_all_data("/proc/pressure/memory").ok()
+            .and_then(|data| parse_psi_some_avg10(&data));
+        if let Some(stalled) = stalled {
+            if stalled > MEMORY_PRESSURE_STALL_THRESHOLD {
+                return true;
+            }
+        }
+        (self.mem_total > 0 &&
+         (self.mem_free as f32) < self.mem_total as f32 * MEMORY_PRESSURE_AVAILABLE_RATIO) ||
+        self.swap_out_rate > MEMORY_PRESSURE_SWAP_OUT_RATE
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the number of CPUs the current process can actually use: if its cgroup has a CPU
+    /// quota (`cpu.max` or `cpu.cfs_quota_us` / `cpu.cfs_period_us`), the quota is rounded up
+    /// to the next CPU. Otherwise, the number of logical CPUs is returned.
+    pub fn get_effective_cpu_count(&self) -> usize {
+        let logical = if self.processors.len() > 1 { self.processors.len() - 1 } else { 1 };
+        match cgroup::get_cpu_quota() {
+            Some(quota) => ::std::cmp::min(logical, ::std::cmp::max(1, quota.ceil() as usize)),
+            None => logical,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the percentage of the memory limit of the current process' cgroup (`memory.max`
+    /// or `memory.limit_in_bytes`) which is used. Inside a container, this is what tells how
+    /// close it is from being OOM-killed, whatever the host memory usage is. Returns `None` if
+    /// the cgroup has no memory limit. It is read on each call.
+    pub fn get_memory_usage_in_cgroup(&self) -> Option<f32> {
+        match cgroup::get_memory_usage() {
+            Some((usage, limit)) if limit > 0 => Some(usage as f32 * 100. / limit as f32),
+            _ => None,
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the amount of RAM installed (in kB). Unlike [`get_total_memory`] (`MemTotal`),
+    /// it includes the memory reserved by the firmware and the kernel (for crash dumps for
+    /// example), so it matches the hardware specifications more closely. It is computed from
+    /// the online memory blocks of `/sys/devices/system/memory` or, if they aren't available,
+    /// from the "System RAM" ranges of `/proc/iomem` (which requires root privileges). Returns
+    /// `None` if neither could be read. It is computed on each call.
+    ///
+    /// [`get_total_memory`]: trait.SystemExt.html#tymethod.get_total_memory
+    pub fn get_physical_memory(&self) -> Option<u64> {
+        get_memory_blocks_size().or_else(|| {
+            get_all_data("/proc/iomem").ok().map(|data| parse_iomem_ram(&data)).filter(|&s| s > 0)
+        }).map(|size| size / 1024)
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the kernel doesn't provide `MemAvailable` (before Linux 3.14). In this
+    /// case, [`get_free_memory`] is estimated as `MemFree + Buffers + Cached`.
+    ///
+    /// [`get_free_memory`]: trait.SystemExt.html#tymethod.get_free_memory
+    pub fn is_free_memory_estimated(&self) -> bool {
+        self.mem_free_estimated
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the size of the RAM locked with `mlock` (in kB). Locked pages can't be reclaimed
+    /// nor swapped out.
+    pub fn get_locked_memory(&self) -> u64 {
+        self.mem_locked
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the value of the given kernel setting, using the same dotted keys as the
+    /// `sysctl` command (`vm.swappiness` is read from `/proc/sys/vm/swappiness` for example).
+    /// Returns `None` if the key doesn't exist or can't be read.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// println!("swappiness: {:?}", s.get_sysctl("vm.swappiness"));
+    /// println!("pid max: {:?}", s.get_sysctl("kernel.pid_max"));
+    /// ```
+    pub fn get_sysctl(&self, key: &str) -> Option<String> {
+        if key.split('.').any(|part| part.is_empty() || part.contains('/')) {
+            return None;
+        }
+        get_all_data(Path::new("/proc/sys").join(key.replace('.', "/")))
+            .ok()
+            .map(|value| value.trim().to_owned())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the value after which pids wrap around, read from `/proc/sys/kernel/pid_max`
+    /// when calling this method. The lower it is, the sooner the pid of an exited process can
+    /// be given to a new one.
+    pub fn get_pid_max(&self) -> Option<pid_t> {
+        self.get_sysctl("kernel.pid_max").and_then(|v| pid_t::from_str(&v).ok())
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Sets how the counter-based metrics are computed on the next refreshes. Take a look at
+    /// [`CounterMode`] for the list of the affected metrics.
+    ///
+    /// [`CounterMode`]: enum.CounterMode.html
+    pub fn set_counter_mode(&mut self, mode: CounterMode) {
+        self.counter_mode = mode;
+        for p in self.process_list.tasks.values_mut() {
+            record_counter_mode(p, mode);
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns how the counter-based metrics are currently computed.
+    pub fn get_counter_mode(&self) -> CounterMode {
+        self.counter_mode
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// When enabled, the CPU usage of the processes seen for the first time by a refresh is
+    /// estimated as their CPU time divided by their age, like with [`CounterMode::SinceBoot`],
+    /// instead of relying on a previous sample which doesn't exist. It makes short-lived
+    /// processes using a lot of CPU visible as soon as they appear. Disabled by default.
+    ///
+    /// [`CounterMode::SinceBoot`]: enum.CounterMode.html#variant.SinceBoot
+    pub fn set_estimate_new_processes_cpu(&mut self, estimate: bool) {
+        self.estimate_new_processes_cpu = estimate;
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the CPU usage of newly seen processes is estimated from their age.
+    pub fn get_estimate_new_processes_cpu(&self) -> bool {
+        self.estimate_new_processes_cpu
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Sets whether the threads of the processes are collected into their [`tasks`] (the
+    /// default). When disabled, `/proc/[pid]/task` isn't read anymore and the `tasks` of all the
+    /// processes are emptied, so [`get_process_list`] only contains processes, making refreshes
+    /// cheaper. The number of threads stays available with [`Process::get_thread_count`].
+    ///
+    /// [`tasks`]: struct.Process.html#structfield.tasks
+    /// [`get_process_list`]: trait.SystemExt.html#tymethod.get_process_list
+    /// [`Process::get_thread_count`]: struct.Process.html#method.get_thread_count
+    pub fn set_collect_threads(&mut self, collect: bool) {
+        self.collect_threads = collect;
+        for p in self.process_list.tasks.values_mut() {
+            record_collect_threads(p, collect);
+            if !collect {
+                p.tasks.clear();
+            }
+        }
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns `true` if the threads of the processes are collected into their `tasks`.
+    pub fn get_collect_threads(&self) -> bool {
+        self.collect_threads
+    }
 
-            for (pid, proc_) in &mut self.process_list.tasks {
-                if !has_been_updated(proc_) {
-                    to_delete.push(*pid);
-                } else {
-                    compute_cpu_usage(proc_, nb_processors, total_time);
-                }
-            }
-            for pid in to_delete {
-                self.process_list.tasks.remove(&pid);
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Sets where the [`name`] of the processes comes from. The processes already in the list
+    /// are renamed accordingly.
+    ///
+    /// [`name`]: struct.Process.html#structfield.name
+    pub fn set_process_name_source(&mut self, source: ProcessNameSource) {
+        self.process_name_source = source;
+        for p in self.process_list.tasks.values_mut() {
+            p.name = get_process_name(&p.cmd, p.get_comm(), source);
+            for task in p.tasks.values_mut() {
+                task.name = p.name.clone();
             }
         }
     }
 
     /// **WARNING**: This method is specific to Linux.
     ///
-    /// Refresh *only* the process corresponding to `pid`.
-    /// Fails if this process is not yet in the process list.
-    pub fn refresh_process(&mut self, pid: pid_t) -> bool {
-        if let Some(proc_) = self.process_list.tasks.get_mut(&pid) {
-            _get_process_data(&Path::new("/proc/").join(pid.to_string()), proc_, self.page_size_kb, pid);
-            true
-        } else {
-            false
-        }
+    /// Returns where the name of the processes currently comes from.
+    pub fn get_process_name_source(&self) -> ProcessNameSource {
+        self.process_name_source
+    }
+
+    /// **WARNING**: This method is specific to Linux.
+    ///
+    /// Returns the memory information of each NUMA node. The list is empty on non-NUMA systems.
+    pub fn get_numa_nodes(&self) -> Vec<NumaNode> {
+        numa::get_numa_nodes()
     }
 }
 
@@ -80,6 +1056,265 @@ fn test_refresh_system()
     assert!(sys.swap_total >= sys.swap_free);
 }
 
+// Taken by the tests keeping processors busy, so they don't skew each other's CPU usage when
+// they run in parallel.
+#[cfg(test)]
+static SPIN_LOCK: ::std::sync::Mutex<()> = ::std::sync::Mutex::new(());
+
+#[cfg(test)]
+fn lock_spinning_tests() -> ::std::sync::MutexGuard<'static, ()> {
+    // A failed spinning test doesn't prevent the other ones from running.
+    SPIN_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[test]
+fn test_total_cpu_usage_needs_two_samples() {
+    let _lock = lock_spinning_tests();
+    let mut sys = System::new();
+    assert_eq!(sys.get_total_cpu_usage(), 0.);
+    // Keeps the processor busy so the second sample has some work time.
+    spin_for(100);
+    sys.refresh_system();
+    let usage = sys.get_total_cpu_usage();
+    assert!(usage > 0. && usage <= 1., "unexpected usage: {}", usage);
+}
+
+#[test]
+fn test_threads_cpu_usage_not_summed_into_process() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    let _lock = lock_spinning_tests();
+    let stop = Arc::new(AtomicBool::new(false));
+    let threads: Vec<_> = (0..4).map(|_| {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {}
+        })
+    }).collect();
+
+    let mut sys = System::new();
+    spin_for(300);
+    sys.refresh_system();
+    sys.refresh_processes();
+    stop.store(true, Ordering::Relaxed);
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    // The 4 spinning threads and the main one can't use more than 500%: the other spinning tests
+    // can't run concurrently. Summing the threads into the process would double the value.
+    let nb_processors = ::std::cmp::min(sys.processors.len() - 1, 4 + 1);
+    let p = sys.get_process(::utils::get_current_pid()).expect("current process not found");
+    assert!(p.tasks.len() >= 4);
+    assert!(p.cpu_usage <= 100. * nb_processors as f32 * 1.15,
+            "{}% for {} busy processors", p.cpu_usage, nb_processors);
+}
+
+// Keeps the current thread busy for `ms` milliseconds.
+#[cfg(test)]
+fn spin_for(ms: u64) {
+    use std::time::{Duration, Instant};
+
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(ms) {}
+}
+
+#[test]
+fn test_process_refresh_computes_cpu_usage() {
+    let _lock = lock_spinning_tests();
+    let pid = ::utils::get_current_pid();
+    let mut sys = System::new();
+    spin_for(200);
+    {
+        let page_size_kb = sys.get_page_size_kb();
+        let p = sys.get_process_mut(pid).expect("current process not found");
+        p.refresh(page_size_kb).expect("failed to refresh the current process");
+        assert!(p.cpu_usage > 70., "{}% after Process::refresh", p.cpu_usage);
+    }
+    // The next full refresh only covers the time elapsed since `Process::refresh`.
+    spin_for(200);
+    sys.refresh_processes();
+    let p = sys.get_process(pid).expect("current process not found");
+    assert!(p.cpu_usage > 70., "{}% after refresh_processes", p.cpu_usage);
+}
+
+#[test]
+fn test_refresh_process_list_for() {
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    let _lock = lock_spinning_tests();
+    let pid = ::utils::get_current_pid();
+    let mut sys = System::new();
+    let (tid_sender, tid_receiver) = channel();
+    let (stop_sender, stop_receiver) = channel::<()>();
+    let thread = thread::spawn(move || {
+        tid_sender.send(unsafe { ::libc::syscall(::libc::SYS_gettid) } as pid_t).unwrap();
+        let _ = stop_receiver.recv();
+    });
+    let tid = tid_receiver.recv().unwrap();
+    spin_for(200);
+    sys.refresh_process_list_for(&[pid]);
+    {
+        let p = sys.get_process(pid).expect("current process not found");
+        assert!(p.cpu_usage > 70., "{}% after refresh_process_list_for", p.cpu_usage);
+        assert!(p.tasks.contains_key(&tid));
+    }
+
+    stop_sender.send(()).unwrap();
+    thread.join().unwrap();
+    sys.refresh_process_list_for(&[pid]);
+    assert!(!sys.get_process(pid).expect("current process not found").tasks.contains_key(&tid));
+
+    // The next full refresh only covers the time elapsed since the previous one.
+    spin_for(200);
+    sys.refresh_processes();
+    let p = sys.get_process(pid).expect("current process not found");
+    assert!(p.cpu_usage > 70., "{}% after refresh_processes", p.cpu_usage);
+}
+
+#[test]
+fn test_environ_of_reused_pid() {
+    let pid = ::utils::get_current_pid();
+    let sys = System::new();
+    let p = sys.get_process(pid).expect("current process not found");
+    assert!(p.get_environ().iter().any(|v| v.starts_with("PATH=")));
+
+    // Another process which used to have the same pid.
+    let p = Process::new(pid, None, p.start_time.saturating_sub(10));
+    assert!(p.get_environ().is_empty());
+}
+
+#[test]
+fn test_proc_read_error_from_io_error() {
+    let e = ProcReadError::from(io::Error::from(io::ErrorKind::NotFound));
+    assert!(matches!(e, ProcReadError::NotFound));
+    let e = ProcReadError::from(io::Error::from(io::ErrorKind::PermissionDenied));
+    assert!(matches!(e, ProcReadError::PermissionDenied));
+    // Returned when reading a file of a process which exited after it was opened.
+    let e = ProcReadError::from(io::Error::from_raw_os_error(ESRCH));
+    assert!(matches!(e, ProcReadError::NotFound));
+    let e = ProcReadError::from(io::Error::from(io::ErrorKind::Interrupted));
+    assert!(matches!(e, ProcReadError::Io(_)));
+}
+
+#[test]
+fn test_parse_uid_and_gid() {
+    let data = "Name:\tbash\nUid:\t1000\t1001\t1001\t1001\nGid:\t100\t101\t101\t101\n";
+    assert_eq!(parse_uid_and_gid(data), Some((1001, 101)));
+    assert_eq!(parse_uid_and_gid("Uid:\t1000\t1001\t1001\t1001\n"), None);
+    assert_eq!(parse_uid_and_gid("Uid:\t1000\nGid:\t100\t101\n"), None);
+    assert_eq!(parse_uid_and_gid(&format!("{}Uid:\t0\t0\t0\t0\n", data)), None);
+}
+
+#[test]
+fn test_pid_reused_during_the_same_second() {
+    let pid = ::utils::get_current_pid();
+    let mut sys = System::new();
+    let start_ticks = {
+        let p = sys.get_process_mut(pid).expect("current process not found");
+        let start_ticks = get_start_ticks_of(p);
+        // Another process started a clock tick later.
+        set_start_ticks(p, start_ticks + 1);
+        p.name = "old".to_owned();
+        start_ticks
+    };
+    assert!(sys.refresh_process(pid));
+    let p = sys.get_process(pid).expect("current process not found");
+    assert_eq!(get_start_ticks_of(p), start_ticks);
+    // The entry was replaced rather than updated.
+    assert_ne!(p.name, "old");
+}
+
+#[test]
+fn test_parse_meminfo() {
+    let info = parse_meminfo(include_str!("../../tests/fixtures/meminfo"));
+    assert_eq!(info, MemInfo {
+        total: 6147400,
+        free: 4566368,
+        available: Some(5620840),
+        buffers: 54924,
+        cached: 1201988,
+        swap_total: 0,
+        swap_free: 0,
+        locked: 8968,
+    });
+
+    // No `MemAvailable` before Linux 3.14.
+    let info = parse_meminfo(include_str!("../../tests/fixtures/meminfo_2.6"));
+    assert_eq!(info.available, None);
+    assert_eq!(info.free + info.buffers + info.cached, 1300800);
+    assert_eq!(info.swap_free, 4075004);
+}
+
+#[test]
+fn test_parse_cpu_lines() {
+    let lines = parse_cpu_lines(include_str!("../../tests/fixtures/stat"));
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], CpuLine {
+        name: "cpu",
+        values: [264524, 1038, 70386, 8537451, 12042, 0, 1870, 0, 0, 0],
+    });
+    assert_eq!(lines[4].name, "cpu3");
+
+    // Only 8 values before Linux 2.6.24.
+    let lines = parse_cpu_lines(include_str!("../../tests/fixtures/stat_2.6.18"));
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].values, [1132, 34, 1441, 11311718, 3675, 127, 438, 0, 0, 0]);
+}
+
+#[test]
+fn test_parse_os_release_value() {
+    assert_eq!(parse_os_release_value("Fedora"), "Fedora");
+    assert_eq!(parse_os_release_value("\"Debian GNU/Linux\""), "Debian GNU/Linux");
+    assert_eq!(parse_os_release_value("'Arch Linux'"), "Arch Linux");
+    assert_eq!(parse_os_release_value("\"My \\\"Linux\\\" \\$5 \\\\o/\""),
+               "My \"Linux\" $5 \\o/");
+    // Nothing is escaped between single quotes.
+    assert_eq!(parse_os_release_value("'a\\b'"), "a\\b");
+}
+
+#[test]
+fn test_parse_stat_counters() {
+    let data = include_str!("../../tests/fixtures/stat");
+    let interrupts = parse_stat_counters(data, "intr");
+    assert_eq!(interrupts.len(), 18);
+    assert_eq!(interrupts[0], 15795371);
+    assert_eq!(interrupts[1], 9);
+    assert_eq!(parse_stat_counters(data, "softirq"),
+               [7328745, 0, 2493474, 2067, 426125, 155613, 0, 16, 2393097, 0, 1858353]);
+    // The `softirq` line only exists since Linux 2.6.31.
+    let data = include_str!("../../tests/fixtures/stat_2.6.18");
+    assert_eq!(parse_stat_counters(data, "intr")[..2], [114930548, 113199788]);
+    assert!(parse_stat_counters(data, "softirq").is_empty());
+}
+
+#[test]
+fn test_first_interrupts_delta() {
+    let sys = System::new();
+    assert_eq!(sys.get_interrupts_total(), 0);
+    assert_eq!(sys.get_softirqs_total(), 0);
+}
+
+#[test]
+fn test_parse_stat_file() {
+    let parts = parse_stat_file(include_str!("../../tests/fixtures/pid_stat_parentheses"))
+        .expect("failed to parse stat file");
+    assert_eq!(parts[0], "1234");
+    // The closing parenthesis of the comm field is dropped.
+    assert_eq!(parts[1], "(my) (weird) proc");
+    assert_eq!(parts[2], "S");
+    assert_eq!(parts[3], "1");
+    assert_eq!(parts[13], "3");
+    assert_eq!(parts[14], "5");
+    assert_eq!(parts[23], "1020");
+    assert_eq!(get_start_ticks(&parts), 4510);
+
+    assert_eq!(parse_stat_file(include_str!("../../tests/fixtures/pid_stat_truncated")), None);
+    assert_eq!(parse_stat_file(""), None);
+}
 
 impl SystemExt for System {
     fn new() -> System {
@@ -89,84 +1324,110 @@ impl SystemExt for System {
             mem_free: 0,
             swap_total: 0,
             swap_free: 0,
+            mem_locked: 0,
+            mem_free_estimated: false,
             processors: Vec::new(),
-            page_size_kb: unsafe { sysconf(_SC_PAGESIZE) as u64 / 1024 },
+            page_size_kb: get_page_size_kb(),
+            clock_ticks: get_clock_ticks(),
             temperatures: component::get_components(),
-            disks: get_all_disks(),
+            disks: get_all_disks(DiskListKind::Physical),
+            disk_list_kind: DiskListKind::Physical,
+            max_disk_failures: None,
+            networks: HashMap::new(),
+            networks_read_at: None,
+            counter_mode: CounterMode::Delta,
+            process_name_source: ProcessNameSource::Cmdline,
+            estimate_new_processes_cpu: false,
+            collect_threads: true,
+            users: UsersCache::default(),
+            meminfo_file: None,
+            stat_file: None,
+            vmstat_file: None,
+            vmstat: HashMap::new(),
+            swap_out_rate: 0.,
+            interrupts: Vec::new(),
+            old_interrupts_total: 0,
+            softirqs_total: 0,
+            old_softirqs_total: 0,
+            system_refreshed_at: Instant::now(),
+            processes_refreshed_at: Instant::now(),
+            disks_refreshed_at: Instant::now(),
         };
         s.refresh_all();
+        // First sample of the network counters, so the next refresh gives the traffic.
+        s.refresh_networks();
         s
     }
 
     fn refresh_system(&mut self) {
-        let data = get_all_data("/proc/meminfo").unwrap();
-
-        for component in &mut self.temperatures {
-            component.update();
+        Component::update_all(&mut self.temperatures);
+        // If a file can't be read, the previous values are kept rather than reset to 0.
+        if let Ok(data) = get_all_data_reusing_file(&mut self.meminfo_file, "/proc/meminfo") {
+            let meminfo = parse_meminfo(&data);
+            self.mem_total = meminfo.total;
+            self.swap_total = meminfo.swap_total;
+            self.swap_free = meminfo.swap_free;
+            self.mem_locked = meminfo.locked;
+            // `MemAvailable` only exists since Linux 3.14. Before that, the kernel itself
+            // estimated the available memory this way.
+            self.mem_free_estimated = meminfo.available.is_none();
+            self.mem_free = meminfo.available
+                                   .unwrap_or(meminfo.free + meminfo.buffers + meminfo.cached);
         }
-        for line in data.split('\n') {
-            let field = match line.split(':').next() {
-                Some("MemTotal") => &mut self.mem_total,
-                Some("MemAvailable") => &mut self.mem_free,
-                Some("SwapTotal") => &mut self.swap_total,
-                Some("SwapFree") => &mut self.swap_free,
-                _ => continue,
+        if let Ok(data) = get_all_data_reusing_file(&mut self.vmstat_file, "/proc/vmstat") {
+            let old_swapped_out = self.get_vmstat_field("pswpout");
+            let elapsed = self.system_refreshed_at.elapsed();
+            let elapsed_secs = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1e9;
+            self.vmstat = parse_vmstat(&data);
+            self.swap_out_rate = match (old_swapped_out, self.get_vmstat_field("pswpout")) {
+                (Some(old), Some(new)) if elapsed_secs > 0. => {
+                    new.saturating_sub(old) as f32 / elapsed_secs
+                }
+                _ => 0.,
             };
-            if let Some(val_str) = line.rsplit(' ').nth(1) {
-                *field = u64::from_str(val_str).unwrap();
-            }
-        }
-        let data = get_all_data("/proc/stat").unwrap();
-        let mut i = 0;
-        let first = self.processors.is_empty();
-        for line in data.split('\n') {
-            if !line.starts_with("cpu") {
-                break;
-            }
-
-            let (parts, _): (Vec<&str>, Vec<&str>) = line.split(' ').partition(|s| !s.is_empty());
-            if first {
-                self.processors.push(new_processor(parts[0], u64::from_str(parts[1]).unwrap(),
-                    u64::from_str(parts[2]).unwrap(),
-                    u64::from_str(parts[3]).unwrap(),
-                    u64::from_str(parts[4]).unwrap(),
-                    u64::from_str(parts[5]).unwrap(),
-                    u64::from_str(parts[6]).unwrap(),
-                    u64::from_str(parts[7]).unwrap(),
-                    u64::from_str(parts[8]).unwrap(),
-                    u64::from_str(parts[9]).unwrap(),
-                    u64::from_str(parts[10]).unwrap()));
-            } else {
-                set_processor(&mut self.processors[i],
-                    u64::from_str(parts[1]).unwrap(),
-                    u64::from_str(parts[2]).unwrap(),
-                    u64::from_str(parts[3]).unwrap(),
-                    u64::from_str(parts[4]).unwrap(),
-                    u64::from_str(parts[5]).unwrap(),
-                    u64::from_str(parts[6]).unwrap(),
-                    u64::from_str(parts[7]).unwrap(),
-                    u64::from_str(parts[8]).unwrap(),
-                    u64::from_str(parts[9]).unwrap(),
-                    u64::from_str(parts[10]).unwrap());
-                i += 1;
-            }
         }
+        self.refresh_cpu();
+        self.system_refreshed_at = Instant::now();
     }
 
     fn refresh_processes(&mut self) {
-        if refresh_procs(&mut self.process_list, "/proc", self.page_size_kb, 0) {
+        let settings = self.get_process_refresh_settings();
+        if refresh_procs(&mut self.process_list, "/proc", 0, settings) {
             self.clear_procs();
         }
+        self.users.update(self.process_list.tasks.values().map(|p| p.uid));
+        self.processes_refreshed_at = Instant::now();
     }
 
     fn refresh_disks(&mut self) {
+        // `statvfs` still succeeds on the underlying directory once a disk has been unmounted
+        // so the mount table is checked as well when disks can be removed.
+        let mounts = if self.max_disk_failures.is_some() {
+            get_all_data("/proc/mounts").map(|data| disk::parse_mounts(&data)).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let io_times = get_all_data("/proc/diskstats").map(|data| disk::parse_diskstats(&data))
+                                                      .unwrap_or_default();
         for disk in &mut self.disks {
-            disk.update();
+            let success = disk.update();
+            let mounted = mounts.is_empty() ||
+                          mounts.iter().any(|m| m.mount_point == disk.get_mount_point());
+            disk::record_update(disk, success && mounted);
+            let io_time = disk.get_name().to_str().and_then(|name| io_times.get(name)).cloned();
+            if let Some((io_time_ms, weighted_io_time_ms)) = io_time {
+                disk::set_io_times(disk, io_time_ms, weighted_io_time_ms);
+            }
+        }
+        if let Some(max_failures) = self.max_disk_failures {
+            self.disks.retain(|disk| disk::get_failed_updates(disk) < max_failures);
         }
+        self.disks_refreshed_at = Instant::now();
     }
 
     fn refresh_disk_list(&mut self) {
-        self.disks = get_all_disks();
+        self.disks = get_all_disks(self.disk_list_kind);
+        self.disks_refreshed_at = Instant::now();
     }
 
     // COMMON PART
@@ -178,6 +1439,9 @@ impl SystemExt for System {
     }
 
     fn get_process(&self, pid: pid_t) -> Option<&Process> {
+        if pid < 0 {
+            return None;
+        }
         self.process_list.tasks.get(&pid)
     }
 
@@ -191,6 +1455,12 @@ impl SystemExt for System {
         ret
     }
 
+    fn get_processes_by_exe_path(&self, path: &Path) -> Vec<&Process> {
+        self.process_list.tasks.values()
+            .filter(|val| !val.exe.is_empty() && Path::new(&val.exe) == path)
+            .collect()
+    }
+
     fn get_processor_list(&self) -> &[Processor] {
         &self.processors[..]
     }
@@ -217,7 +1487,7 @@ impl SystemExt for System {
 
     // need to be checked
     fn get_used_swap(&self) -> u64 {
-        self.swap_total - self.swap_free
+        self.swap_total.saturating_sub(self.swap_free)
     }
 
     fn get_components_list(&self) -> &[Component] {
@@ -227,6 +1497,37 @@ impl SystemExt for System {
     fn get_disks(&self) -> &[Disk] {
         &self.disks[..]
     }
+
+    fn get_name(&self) -> Option<String> {
+        for file in &["/etc/os-release", "/usr/lib/os-release"] {
+            if let Ok(data) = get_all_data(file) {
+                return data.lines()
+                           .find(|line| line.starts_with("NAME="))
+                           .map(|line| parse_os_release_value(&line[5..]))
+                           .or_else(|| Some("Linux".to_owned()));
+            }
+        }
+        Some("Linux".to_owned())
+    }
+
+    fn get_available_entropy(&self) -> Option<usize> {
+        get_all_data("/proc/sys/kernel/random/entropy_avail")
+            .ok()
+            .and_then(|data| usize::from_str(data.trim()).ok())
+    }
+
+    fn get_open_file_descriptors(&self) -> (u64, u64) {
+        // The file contains "<allocated> <allocated but unused> <maximum>". The second value is
+        // always 0 since Linux 2.6.
+        let data = get_all_data("/proc/sys/fs/file-nr").unwrap_or_default();
+        let values: Vec<u64> = data.split_whitespace()
+                                   .map(|v| u64::from_str(v).unwrap_or(0))
+                                   .collect();
+        match values.as_slice() {
+            [allocated, unused, max] => (allocated.saturating_sub(*unused), *max),
+            _ => (0, 0),
+        }
+    }
 }
 
 impl Default for System {
@@ -235,19 +1536,193 @@ impl Default for System {
     }
 }
 
-pub fn get_all_data<P: AsRef<Path>>(file_path: P) -> io::Result<String> {
-    use std::error::Error;
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Error returned when a `/proc` file couldn't be read.
+#[derive(Debug)]
+pub enum ProcReadError {
+    /// The file doesn't exist (the process is gone for example).
+    NotFound,
+    /// `sysinfo` doesn't have enough rights to read the file (it belongs to another user for
+    /// example).
+    PermissionDenied,
+    /// The file content couldn't be parsed: it isn't valid UTF-8 or doesn't have the expected
+    /// format.
+    Parse,
+    /// The process exited and its pid now belongs to a new process (their start times differ).
+    PidReused,
+    /// Any other I/O error.
+    Io(io::Error),
+}
+
+impl From<io::Error> for ProcReadError {
+    fn from(e: io::Error) -> ProcReadError {
+        match e.kind() {
+            io::ErrorKind::NotFound => ProcReadError::NotFound,
+            io::ErrorKind::PermissionDenied => ProcReadError::PermissionDenied,
+            // Reading a file of a process which exited in the meantime returns `ESRCH`.
+            _ if e.raw_os_error() == Some(ESRCH) => ProcReadError::NotFound,
+            _ => ProcReadError::Io(e),
+        }
+    }
+}
+
+impl fmt::Display for ProcReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcReadError::NotFound => write!(f, "file not found"),
+            ProcReadError::PermissionDenied => write!(f, "permission denied"),
+            ProcReadError::Parse => write!(f, "couldn't parse file content"),
+            ProcReadError::PidReused => write!(f, "pid reused by another process"),
+            ProcReadError::Io(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ProcReadError {}
+
+/// Reads the whole content of a `/proc` file.
+pub fn read_proc_file<P: AsRef<Path>>(file_path: P) -> Result<String, ProcReadError> {
     let mut file = File::open(file_path.as_ref())?;
+    count_file_read();
     let mut data = vec![0; 16385];
 
-    let size = file.read(&mut data).unwrap();
+    let size = file.read(&mut data)?;
     data.truncate(size);
+    String::from_utf8(data).map_err(|_| ProcReadError::Parse)
+}
+
+pub fn get_all_data<P: AsRef<Path>>(file_path: P) -> io::Result<String> {
+    let mut file = File::open(file_path.as_ref())?;
+    get_all_data_from_file(&mut file)
+}
+
+// Reads the content of a file kept opened in `file`, opening it first if needed. Seeking back to
+// the start of a `/proc` file and reading it again gives an up-to-date content.
+fn get_all_data_reusing_file(file: &mut Option<File>, file_path: &str) -> io::Result<String> {
+    if let Some(ref mut f) = *file {
+        if f.seek(SeekFrom::Start(0)).is_ok() {
+            if let Ok(data) = get_all_data_from_file(f) {
+                return Ok(data);
+            }
+        }
+    }
+    // Either the file wasn't opened yet or reusing it failed: let's open it again.
+    *file = None;
+    let mut f = File::open(file_path)?;
+    let data = get_all_data_from_file(&mut f)?;
+    *file = Some(f);
+    Ok(data)
+}
+
+fn get_all_data_from_file(file: &mut File) -> io::Result<String> {
+    count_file_read();
+    let mut data = Vec::with_capacity(16385);
+
+    // `/proc/stat` can be bigger than a single read on machines with a lot of interrupts.
+    file.read_to_end(&mut data)?;
     let data = String::from_utf8(data).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.description()))?;
     Ok(data)
 }
 
-fn refresh_procs<P: AsRef<Path>>(proc_list: &mut Process, path: P, page_size_kb: u64,
-                                 pid: pid_t) -> bool {
+// Computes the CPU usage of the tasks of `proc_` and removes the ones which haven't been
+// updated. Threads are handled the same way as processes but their usage is never added to
+// their process' one: the `stat` file of a process already accounts for all its threads.
+fn clear_tasks(proc_: &mut Process, mode: CounterMode, nb_processors: u64,
+               total_times: (u64, u64), uptime: f32, clock_ticks: u64, estimate_new: bool) {
+    let mut to_delete = Vec::new();
+
+    for (pid, task) in &mut proc_.tasks {
+        if !has_been_updated(task) {
+            to_delete.push(*pid);
+            continue;
+        }
+        record_counter_mode(task, mode);
+        if mode == CounterMode::SinceBoot || (estimate_new && is_first_sample(task)) {
+            compute_cpu_usage_since_start(task, uptime, clock_ticks);
+        } else {
+            compute_cpu_usage(task, nb_processors, total_times.0, total_times.1);
+        }
+        clear_tasks(task, mode, nb_processors, total_times, uptime, clock_ticks, estimate_new);
+    }
+    for pid in to_delete {
+        proc_.tasks.remove(&pid);
+    }
+}
+
+// Computes the CPU usage of a process refreshed on its own and of its threads, removing the
+// ones which exited, from the content of a `/proc/stat` file. The CPU usage is computed since
+// the previous refresh of the process, whichever it was.
+pub fn compute_refreshed_process_cpu_usage(p: &mut Process, stat_data: &str, mode: CounterMode,
+                                           clock_ticks: u64, estimate_new: bool) {
+    let lines = parse_cpu_lines(stat_data);
+    let total_time = match lines.first() {
+        Some(line) => {
+            let v = line.values;
+            CpuValues::new_with_values(v[0], v[1], v[2], v[3], v[4], v[5], v[6], v[7], v[8],
+                                       v[9]).total_time()
+        }
+        None => return,
+    };
+    let nb_processors = ::std::cmp::max(1, lines.len() as u64 - 1);
+    let uptime = if mode == CounterMode::SinceBoot || estimate_new { get_uptime() } else { 0. };
+    record_counter_mode(p, mode);
+    if mode == CounterMode::SinceBoot || (estimate_new && is_first_sample(p)) {
+        compute_cpu_usage_since_start(p, uptime, clock_ticks);
+    } else {
+        compute_cpu_usage(p, nb_processors, total_time, 0);
+    }
+    clear_tasks(p, mode, nb_processors, (total_time, 0), uptime, clock_ticks, estimate_new);
+}
+
+// Returns the start time (in seconds since boot) of the content of a `stat` file.
+// Returns the start time (in clock ticks since boot). It's compared as is to detect reused pids:
+// once converted to seconds, two processes started during the same second can't be told apart.
+pub fn get_start_ticks(parts: &[&str]) -> u64 {
+    u64::from_str(parts[21]).unwrap_or(0)
+}
+
+// `sysconf` returns -1 on error, in which case the most common values are used.
+fn get_page_size_kb() -> u64 {
+    match unsafe { sysconf(_SC_PAGESIZE) } {
+        size if size > 0 => size as u64 / 1024,
+        _ => 4,
+    }
+}
+
+pub fn get_clock_ticks() -> u64 {
+    match unsafe { sysconf(_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+fn get_uptime() -> f32 {
+    get_all_data("/proc/uptime").ok()
+        .and_then(|d| d.split_whitespace().next().and_then(|s| f32::from_str(s).ok()))
+        .unwrap_or(0.)
+}
+
+fn get_process_name(cmd: &[String], comm: &str, source: ProcessNameSource) -> String {
+    match source {
+        ProcessNameSource::Cmdline => {
+            cmd.first().and_then(|c| c.split('/').last()).unwrap_or("").to_owned()
+        }
+        ProcessNameSource::Comm => comm.to_owned(),
+    }
+}
+
+// Settings of the `System` used to refresh the processes.
+#[derive(Clone, Copy)]
+pub struct ProcessRefreshSettings {
+    pub page_size_kb: u64,
+    pub clock_ticks: u64,
+    pub name_source: ProcessNameSource,
+    pub collect_threads: bool,
+}
+
+fn refresh_procs<P: AsRef<Path>>(proc_list: &mut Process, path: P, pid: pid_t,
+                                 settings: ProcessRefreshSettings) -> bool {
     if let Ok(d) = fs::read_dir(path.as_ref()) {
         for entry in d {
             if !entry.is_ok() {
@@ -257,7 +1732,7 @@ fn refresh_procs<P: AsRef<Path>>(proc_list: &mut Process, path: P, page_size_kb:
             let entry = entry.path();
 
             if entry.is_dir() {
-                _get_process_data(entry.as_path(), proc_list, page_size_kb, pid);
+                _get_process_data(entry.as_path(), proc_list, pid, settings);
             }
         }
         true
@@ -266,24 +1741,279 @@ fn refresh_procs<P: AsRef<Path>>(proc_list: &mut Process, path: P, page_size_kb:
     }
 }
 
-fn update_time_and_memory(path: &Path, entry: &mut Process, parts: &[&str], page_size_kb: u64,
-                          parent_memory: u64, pid: pid_t) {
+fn update_time_and_memory(path: &Path, entry: &mut Process, parts: &[&str], parent_memory: u64,
+                          pid: pid_t, settings: ProcessRefreshSettings) {
     //entry.name = parts[1][1..].to_owned();
     //entry.name.pop();
     // we get the rss
     {
-        entry.memory = u64::from_str(parts[23]).unwrap() * page_size_kb;
+        let old_memory = entry.memory;
+        entry.memory = u64::from_str(parts[23]).unwrap_or(0) * settings.page_size_kb;
         if entry.memory >= parent_memory {
             entry.memory -= parent_memory;
         }
+        set_memory_delta(entry, entry.memory as i64 - old_memory as i64);
         set_time(entry,
-                 u64::from_str(parts[13]).unwrap(),
-                 u64::from_str(parts[14]).unwrap());
+                 u64::from_str(parts[13]).unwrap_or(0),
+                 u64::from_str(parts[14]).unwrap_or(0));
+        set_children_time(entry,
+                          u64::from_str(parts[15]).unwrap_or(0),
+                          u64::from_str(parts[16]).unwrap_or(0));
+        set_flags(entry, u32::from_str(parts[8]).unwrap_or(0));
+        set_process_group(entry, pid_t::from_str(parts[4]).unwrap_or(0));
+        // The parenthesis before the command name is kept by `parse_stat_file`.
+        set_comm(entry, parts[1].get(1..).unwrap_or(""));
+        if let Some(Ok(policy)) = parts.get(40).map(|p| u32::from_str(p)) {
+            set_scheduling_policy(entry, policy);
+        }
+    }
+    record_collect_threads(entry, settings.collect_threads);
+    record_clock_ticks(entry, settings.clock_ticks);
+    if settings.collect_threads {
+        // Tasks take the name of their process, whatever its source. Threads don't have
+        // threads themselves.
+        refresh_procs(entry, path.join(Path::new("task")), pid, ProcessRefreshSettings {
+            name_source: ProcessNameSource::Cmdline,
+            collect_threads: false,
+            ..settings
+        });
+    }
+}
+
+// Content of the `/proc/meminfo` file (in kB).
+#[derive(Debug, Default, PartialEq)]
+struct MemInfo {
+    total: u64,
+    free: u64,
+    // `None` before Linux 3.14.
+    available: Option<u64>,
+    buffers: u64,
+    cached: u64,
+    swap_total: u64,
+    swap_free: u64,
+    locked: u64,
+}
+
+fn parse_meminfo(data: &str) -> MemInfo {
+    let mut info = MemInfo::default();
+    for line in data.lines() {
+        let mut parts = line.splitn(2, ':');
+        let field = match parts.next() {
+            Some("MemTotal") => &mut info.total,
+            Some("MemFree") => &mut info.free,
+            Some("MemAvailable") => info.available.get_or_insert(0),
+            Some("Buffers") => &mut info.buffers,
+            Some("Cached") => &mut info.cached,
+            Some("SwapTotal") => &mut info.swap_total,
+            Some("SwapFree") => &mut info.swap_free,
+            Some("Mlocked") => &mut info.locked,
+            _ => continue,
+        };
+        if let Some(Ok(val)) = parts.next().and_then(|v| v.split_whitespace().next())
+                                           .map(u64::from_str) {
+            *field = val;
+        }
+    }
+    info
+}
+
+// A "cpu" line of the `/proc/stat` file.
+#[derive(Debug, PartialEq)]
+struct CpuLine<'a> {
+    name: &'a str,
+    // user, nice, system, idle, iowait, irq, softirq, steal, guest and guest_nice times. The
+    // ones missing on older kernels are set to `0`.
+    values: [u64; 10],
+}
+
+fn parse_cpu_lines<'a>(data: &'a str) -> Vec<CpuLine<'a>> {
+    let mut ret = Vec::new();
+    for line in data.lines() {
+        if !line.starts_with("cpu") {
+            break;
+        }
+        let mut parts = line.split_whitespace();
+        let name = match parts.next() {
+            Some(name) => name,
+            None => break,
+        };
+        let mut values = [0; 10];
+        for (value, part) in values.iter_mut().zip(parts) {
+            *value = u64::from_str(part).unwrap_or(0);
+        }
+        ret.push(CpuLine { name: name, values: values });
+    }
+    ret
+}
+
+// Returns the "avg10" value (in percent) of the "some" line of a `/proc/pressure/*` file, like
+// "some avg10=0.31 avg60=0.12 avg300=0.04 total=1234".
+fn parse_psi_some_avg10(data: &str) -> Option<f32> {
+    data.lines()
+        .find(|line| line.starts_with("some "))
+        .and_then(|line| line.split_whitespace().find(|v| v.starts_with("avg10=")))
+        .and_then(|v| f32::from_str(&v[6..]).ok())
+}
+
+// Lines look like "pgfault 30588427".
+fn parse_vmstat(data: &str) -> HashMap<String, u64> {
+    let mut ret = HashMap::new();
+    for line in data.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(key), Some(Ok(value))) = (parts.next(), parts.next().map(u64::from_str)) {
+            ret.insert(key.to_owned(), value);
+        }
+    }
+    ret
+}
+
+// Returns the size (in bytes) of the online memory blocks.
+fn get_memory_blocks_size() -> Option<u64> {
+    let root = Path::new("/sys/devices/system/memory");
+    let block_size = get_all_data(root.join("block_size_bytes"))
+        .ok()
+        .and_then(|s| u64::from_str_radix(s.trim(), 16).ok())?;
+    let mut nb_blocks = 0;
+    for entry in fs::read_dir(root).ok()?.filter_map(|e| e.ok()) {
+        if !entry.file_name().to_string_lossy().starts_with("memory") {
+            continue;
+        }
+        // Blocks which can't be offlined don't always have a "state" file.
+        match get_all_data(entry.path().join("state")) {
+            Ok(ref state) if state.trim() != "online" => {}
+            _ => nb_blocks += 1,
+        }
+    }
+    if nb_blocks == 0 {
+        None
+    } else {
+        Some(nb_blocks * block_size)
+    }
+}
+
+// Returns the size (in bytes) of the top-level "System RAM" ranges of a `/proc/iomem` file.
+// Without root privileges, all the addresses are 0.
+fn parse_iomem_ram(data: &str) -> u64 {
+    let mut total = 0;
+    for line in data.lines() {
+        // Nested ranges are indented.
+        if line.starts_with(' ') {
+            continue;
+        }
+        let mut parts = line.splitn(2, " : ");
+        if let (Some(range), Some("System RAM")) = (parts.next(), parts.next()) {
+            let mut bounds = range.splitn(2, '-').map(|b| u64::from_str_radix(b, 16));
+            if let (Some(Ok(start)), Some(Ok(end))) = (bounds.next(), bounds.next()) {
+                if end > start {
+                    total += end - start + 1;
+                }
+            }
+        }
+    }
+    total
+}
+
+// Returns the value of `key` in the first entry of the `/proc/cpuinfo` file, whose lines look
+// like "model name\t: Intel(R) Core(TM) i7".
+fn parse_cpuinfo_field(data: &str, key: &str) -> Option<String> {
+    for line in data.lines() {
+        // Entries are separated by empty lines.
+        if line.trim().is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        if let (Some(k), Some(value)) = (parts.next(), parts.next()) {
+            if k.trim() == key && !value.trim().is_empty() {
+                return Some(value.trim().to_owned());
+            }
+        }
+    }
+    None
+}
+
+// Values of the `os-release` file follow the shell syntax: they can be enclosed in double or
+// single quotes, and backslashes escape the next character outside of single quotes.
+fn parse_os_release_value(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].to_owned();
+    }
+    let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+    let mut ret = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            ret.extend(chars.next());
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+// Returns the values of the `key` line of the `/proc/stat` file.
+fn parse_stat_counters(data: &str, key: &str) -> Vec<u64> {
+    for line in data.lines() {
+        let mut parts = line.split_whitespace();
+        if parts.next() == Some(key) {
+            return parts.map(|v| u64::from_str(v).unwrap_or(0)).collect();
+        }
+    }
+    Vec::new()
+}
+
+// Returns `None` if `data` doesn't look like the content of a `stat` file.
+pub fn parse_stat_file(data: &str) -> Option<Vec<&str>> {
+    // The stat file is "interesting" to parse, because spaces cannot
+    // be used as delimiters. The second field stores the command name
+    // sourrounded by parentheses. Unfortunately, whitespace and
+    // parentheses are legal parts of the command, so parsing has to
+    // proceed like this: The first field is delimited by the first
+    // whitespace, the second field is everything until the last ')'
+    // in the entire string. All other fields are delimited by
+    // whitespace.
+
+    let mut parts = Vec::new();
+    let mut data_it = data.splitn(2, ' ');
+    parts.push(data_it.next()?);
+    // The following loses the ) from the input, but that's ok because
+    // we're not using it anyway.
+    let mut data_it = data_it.next()?.rsplitn(2, ')');
+    let data = data_it.next()?;
+    parts.push(data_it.next()?);
+    parts.extend(data.split_whitespace());
+    // Fields up to the RSS one (the 24th) exist since Linux 2.6.
+    if parts.len() < 24 {
+        return None;
+    }
+    Some(parts)
+}
+
+pub fn refresh_process_data(entry: &mut Process,
+                            settings: ProcessRefreshSettings) -> Result<(), ProcReadError> {
+    let path = Path::new("/proc/").join(entry.pid.to_string());
+    let data = read_proc_file(path.join("stat"))?;
+    let parts = parse_stat_file(&data).ok_or(ProcReadError::Parse)?;
+    count_process_scanned();
+    if get_start_ticks(&parts) != get_start_ticks_of(entry) {
+        return Err(ProcReadError::PidReused);
+    }
+    entry.status = parts[2].chars().next().map(ProcessStatus::from);
+    let pid = entry.pid;
+    set_stat_data(entry, &data);
+    if let Ok(status_data) = read_proc_file(path.join("status")) {
+        update_status_fields(entry, &status_data);
     }
-    refresh_procs(entry, path.join(Path::new("task")), page_size_kb, pid);
+    update_time_and_memory(&path, entry, &parts, 0, pid, settings);
+    Ok(())
 }
 
-fn _get_process_data(path: &Path, proc_list: &mut Process, page_size_kb: u64, pid: pid_t) {
+fn _get_process_data(path: &Path, proc_list: &mut Process, pid: pid_t,
+                     settings: ProcessRefreshSettings) {
     if let Some(Ok(nb)) = path.file_name().and_then(|x| x.to_str()).map(pid_t::from_str) {
         if nb == pid {
             return
@@ -291,83 +2021,65 @@ fn _get_process_data(path: &Path, proc_list: &mut Process, page_size_kb: u64, pi
         let mut tmp = PathBuf::from(path);
 
         tmp.push("stat");
-        let data = get_all_data(&tmp).unwrap();
-
-        // The stat file is "interesting" to parse, because spaces cannot
-        // be used as delimiters. The second field stores the command name
-        // sourrounded by parentheses. Unfortunately, whitespace and
-        // parentheses are legal parts of the command, so parsing has to
-        // proceed like this: The first field is delimited by the first
-        // whitespace, the second field is everything until the last ')'
-        // in the entire string. All other fields are delimited by
-        // whitespace.
-
-        let mut parts = Vec::new();
-        let mut data_it = data.splitn(2, ' ');
-        parts.push(data_it.next().unwrap());
-        // The following loses the ) from the input, but that's ok because
-        // we're not using it anyway.
-        let mut data_it = data_it.next().unwrap().rsplitn(2, ')');
-        let data = data_it.next().unwrap();
-        parts.push(data_it.next().unwrap());
-        parts.extend(data.split_whitespace());
+        // If the process is gone or can't be read, there is nothing to update.
+        let data = match read_proc_file(&tmp) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let parts = match parse_stat_file(&data) {
+            Some(parts) => parts,
+            None => return,
+        };
+        count_process_scanned();
         let parent_memory = proc_list.memory;
+        let start_ticks = get_start_ticks(&parts);
         if let Some(ref mut entry) = proc_list.tasks.get_mut(&nb) {
-            update_time_and_memory(path, entry, &parts, page_size_kb, parent_memory, nb);
-            return;
+            if get_start_ticks_of(entry) == start_ticks {
+                set_stat_data(entry, &data);
+                if let Ok(status_data) = read_proc_file(path.join("status")) {
+                    update_status_fields(entry, &status_data);
+                }
+                update_time_and_memory(path, entry, &parts, parent_memory, nb, settings);
+                return;
+            }
         }
+        // If the pid was reused, the new process replaces the old one.
+        proc_list.tasks.remove(&nb);
 
         let parent_pid = if proc_list.pid != 0 {
             Some(proc_list.pid)
         } else {
-            match pid_t::from_str(parts[3]).unwrap() {
-                0 => None,
-                p => Some(p),
+            match pid_t::from_str(parts[3]) {
+                Ok(0) | Err(_) => None,
+                Ok(p) => Some(p),
             }
         };
 
         let mut p = Process::new(nb,
                                  parent_pid,
-                                 u64::from_str(parts[21]).unwrap() /
-                                 unsafe { sysconf(_SC_CLK_TCK) } as u64);
+                                 start_ticks / settings.clock_ticks);
+        set_start_ticks(&mut p, start_ticks);
 
         p.status = parts[2].chars().next().and_then(|c| Some(ProcessStatus::from(c)));
 
         tmp = PathBuf::from(path);
         tmp.push("status");
-        let status_data = get_all_data(&tmp).unwrap();
-
-        // We're only interested in the lines starting with Uid: and Gid:
-        // here. From these lines, we're looking at the second entry to get
-        // the effective u/gid.
-
-        let f = |h: &str, n: &str| -> Option<uid_t> {
-            if h.starts_with(n) {
-                h.split_whitespace().nth(2).unwrap().parse().ok()
-            } else {
-                None
-            }
+        let status_data = match read_proc_file(&tmp) {
+            Ok(data) => data,
+            Err(_) => return,
         };
-        let mut set_uid = false;
-        let mut set_gid = false;
-        for line in status_data.lines() {
-            if let Some(u) = f(line, "Uid:") {
-                assert!(!set_uid);
-                set_uid = true;
-                p.uid = u;
-            }
-            if let Some(g) = f(line, "Gid:") {
-                assert!(!set_gid);
-                set_gid = true;
-                p.gid = g;
+
+        match parse_uid_and_gid(&status_data) {
+            Some((uid, gid)) => {
+                p.uid = uid;
+                p.gid = gid;
             }
+            None => return,
         }
-        assert!(set_uid && set_gid);
 
         if proc_list.pid != 0 {
             p.cmd = proc_list.cmd.clone();
             p.name = proc_list.name.clone();
-            p.environ = proc_list.environ.clone();
             p.exe = proc_list.exe.clone();
             p.cwd = proc_list.cwd.clone();
             p.root = proc_list.root.clone();
@@ -375,58 +2087,100 @@ fn _get_process_data(path: &Path, proc_list: &mut Process, page_size_kb: u64, pi
             tmp = PathBuf::from(path);
             tmp.push("cmdline");
             p.cmd = copy_from_file(&tmp);
-            p.name = p.cmd[0].split('/').last().unwrap().to_owned();
-            tmp = PathBuf::from(path);
-            tmp.push("environ");
-            p.environ = copy_from_file(&tmp);
+            p.name = get_process_name(&p.cmd, parts[1].get(1..).unwrap_or(""), settings.name_source);
             tmp = PathBuf::from(path);
             tmp.push("exe");
 
-            let s = read_link(tmp.to_str().unwrap());
-
-            if s.is_ok() {
-                p.exe = s.unwrap().to_str().unwrap().to_owned();
+            // Paths aren't necessarily valid UTF-8.
+            if let Ok(exe) = read_link(&tmp) {
+                p.exe = exe.to_string_lossy().into_owned();
             }
             tmp = PathBuf::from(path);
             tmp.push("cwd");
-            p.cwd = realpath(&tmp).to_str().unwrap().to_owned();
+            p.cwd = realpath(&tmp).to_string_lossy().into_owned();
             tmp = PathBuf::from(path);
             tmp.push("root");
-            p.root = realpath(&tmp).to_str().unwrap().to_owned();
+            p.root = realpath(&tmp).to_string_lossy().into_owned();
         }
 
-        update_time_and_memory(path, &mut p, &parts, page_size_kb, proc_list.memory, nb);
+        set_stat_data(&mut p, &data);
+        // The status file was already read for the uid and gid.
+        update_status_fields(&mut p, &status_data);
+        update_time_and_memory(path, &mut p, &parts, proc_list.memory, nb, settings);
+        // There is no previous value to compare with.
+        set_memory_delta(&mut p, 0);
         proc_list.tasks.insert(nb, p);
     }
 }
 
-fn copy_from_file(entry: &Path) -> Vec<String> {
-    match File::open(entry.to_str().unwrap()) {
+// We're only interested in the lines starting with Uid: and Gid: here. From these lines, we're
+// looking at the second entry to get the effective u/gid. Returns `None` if one of them is
+// missing, malformed or listed twice.
+fn parse_uid_and_gid(status_data: &str) -> Option<(uid_t, gid_t)> {
+    let mut uid = None;
+    let mut gid = None;
+    for line in status_data.lines() {
+        let mut parts = line.split_whitespace();
+        let value = match parts.next() {
+            Some("Uid:") => &mut uid,
+            Some("Gid:") => &mut gid,
+            _ => continue,
+        };
+        if value.is_some() {
+            return None;
+        }
+        *value = Some(parts.nth(1)?.parse().ok()?);
+    }
+    Some((uid?, gid?))
+}
+
+pub fn copy_from_file(entry: &Path) -> Vec<String> {
+    match File::open(entry) {
         Ok(mut f) => {
+            count_file_read();
             let mut data = vec![0; 16384];
 
-            let size = f.read(&mut data).unwrap();
+            let size = match f.read(&mut data) {
+                Ok(size) => size,
+                Err(_) => return Vec::new(),
+            };
             data.truncate(size);
-            let d = String::from_utf8(data).expect("not utf8?");
+            let d = String::from_utf8_lossy(&data);
             d.split('\0').map(|x| x.to_owned()).collect()
         },
         Err(_) => Vec::new()
     }
 }
 
-fn get_all_disks() -> Vec<Disk> {
-    #[allow(or_fun_call)]
-    let content = get_all_data("/proc/mounts").unwrap_or(String::new());
-    let disks = content.lines()
-        .filter(|line| line.trim_left().starts_with("/dev/sd"));
+fn get_all_disks(list_kind: DiskListKind) -> Vec<Disk> {
+    let mounts = get_all_data("/proc/mounts").map(|data| disk::parse_mounts(&data))
+                                             .unwrap_or_default();
     let mut ret = vec![];
+    let mut btrfs_file_systems = HashSet::new();
 
-    for line in disks {
-        let mut split = line.split(' ');
-        if let (Some(name), Some(mountpt), Some(fs)) = (split.next(), split.next(), split.next())
-        {
-            ret.push(disk::new(name[5..].as_ref(), Path::new(mountpt), fs.as_bytes()));
+    for mount in &mounts {
+        let kind = match disk::find_kind_for_mount(&mount.device, &mount.file_system) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        // Each btrfs subvolume is mounted separately from the same device, so only the first
+        // mount is kept to avoid counting the file system's space several times. A file system
+        // spanning several devices can be mounted from any of them, so it's identified by its
+        // UUID when possible.
+        if mount.file_system == "btrfs" {
+            let fsid = disk::get_btrfs_fsid(&mount.device)
+                            .unwrap_or_else(|| OsString::from(&mount.device));
+            if !btrfs_file_systems.insert(fsid) {
+                continue;
+            }
         }
+        let name = match kind {
+            DiskKind::Physical | DiskKind::RemovableMedia => &mount.device[5..],
+            _ if list_kind == DiskListKind::Physical => continue,
+            _ => &mount.device,
+        };
+        ret.push(disk::new(name.as_ref(), &mount.mount_point, mount.file_system.as_bytes(), kind,
+                           &mount.options));
     }
     ret
 }
@@ -0,0 +1,60 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::mem;
+use std::ptr;
+use std::time::SystemTime;
+
+use libc::{c_char, getpwuid_r, passwd, uid_t};
+
+// Cache of the user names, resolved once per uid until `/etc/passwd` changes.
+#[derive(Debug, Default)]
+pub struct UsersCache {
+    names: HashMap<uid_t, Option<String>>,
+    passwd_mtime: Option<SystemTime>,
+}
+
+impl UsersCache {
+    // Resolves the given uids which aren't known yet. Everything is resolved again if
+    // `/etc/passwd` was modified since the previous call.
+    pub fn update<I: Iterator<Item = uid_t>>(&mut self, uids: I) {
+        let mtime = fs::metadata("/etc/passwd").and_then(|m| m.modified()).ok();
+        if mtime != self.passwd_mtime {
+            self.names.clear();
+            self.passwd_mtime = mtime;
+        }
+        for uid in uids {
+            self.names.entry(uid).or_insert_with(|| get_user_name(uid));
+        }
+    }
+
+    pub fn get(&self, uid: uid_t) -> Option<&str> {
+        self.names.get(&uid).and_then(|name| name.as_ref()).map(|name| name.as_str())
+    }
+}
+
+fn get_user_name(uid: uid_t) -> Option<String> {
+    let mut buffer = vec![0 as c_char; 1024];
+    loop {
+        let mut pwd: passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        let ret = unsafe {
+            getpwuid_r(uid, &mut pwd, buffer.as_mut_ptr(), buffer.len(), &mut result)
+        };
+        if ret == ::libc::ERANGE && buffer.len() < 65536 {
+            let len = buffer.len() * 2;
+            buffer.resize(len, 0);
+            continue;
+        }
+        if ret != 0 || result.is_null() || pwd.pw_name.is_null() {
+            return None;
+        }
+        return Some(unsafe { CStr::from_ptr(pwd.pw_name) }.to_string_lossy().into_owned());
+    }
+}
@@ -0,0 +1,93 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use std::path::Path;
+
+use super::system::get_all_data;
+
+/// **WARNING**: This enum is specific to Linux.
+///
+/// Enum describing the environment the system is running in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Virtualization {
+    /// Bare-metal (or undetected virtualization).
+    None,
+    /// KVM virtual machine.
+    Kvm,
+    /// QEMU virtual machine (without KVM).
+    Qemu,
+    /// VMware virtual machine.
+    Vmware,
+    /// Xen virtual machine.
+    Xen,
+    /// Hyper-V virtual machine.
+    HyperV,
+    /// VirtualBox virtual machine.
+    VirtualBox,
+    /// Virtual machine from an unknown hypervisor.
+    OtherVm,
+    /// Docker container.
+    Docker,
+    /// Podman container.
+    Podman,
+    /// LXC container.
+    Lxc,
+    /// Container from an unknown engine (like a Kubernetes pod one).
+    OtherContainer,
+}
+
+fn get_container() -> Option<Virtualization> {
+    if Path::new("/.dockerenv").exists() {
+        return Some(Virtualization::Docker);
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some(Virtualization::Podman);
+    }
+    let cgroup = get_all_data("/proc/1/cgroup").unwrap_or_default();
+    if cgroup.contains("/docker") {
+        Some(Virtualization::Docker)
+    } else if cgroup.contains("/libpod") {
+        Some(Virtualization::Podman)
+    } else if cgroup.contains("/lxc") {
+        Some(Virtualization::Lxc)
+    } else if cgroup.contains("/kubepods") {
+        Some(Virtualization::OtherContainer)
+    } else {
+        None
+    }
+}
+
+fn get_vm() -> Option<Virtualization> {
+    let read = |file| get_all_data(Path::new("/sys/class/dmi/id").join(file)).unwrap_or_default();
+    let dmi = format!("{} {}", read("sys_vendor"), read("product_name"));
+
+    if dmi.contains("KVM") {
+        Some(Virtualization::Kvm)
+    } else if dmi.contains("QEMU") {
+        Some(Virtualization::Qemu)
+    } else if dmi.contains("VMware") {
+        Some(Virtualization::Vmware)
+    } else if dmi.contains("VirtualBox") {
+        Some(Virtualization::VirtualBox)
+    } else if dmi.contains("Xen") || Path::new("/proc/xen").exists() {
+        Some(Virtualization::Xen)
+    } else if dmi.contains("Microsoft Corporation") && dmi.contains("Virtual Machine") {
+        Some(Virtualization::HyperV)
+    } else if get_all_data("/proc/cpuinfo").unwrap_or_default()
+                                           .lines()
+                                           .filter(|l| l.starts_with("flags"))
+                                           .any(|l| l.split_whitespace().any(|f| f == "hypervisor")) {
+        Some(Virtualization::OtherVm)
+    } else {
+        None
+    }
+}
+
+// Containers are checked first since a container running inside a virtual machine would
+// otherwise be reported as the virtual machine.
+pub fn get_virtualization() -> Virtualization {
+    get_container().or_else(get_vm).unwrap_or(Virtualization::None)
+}
@@ -26,6 +26,11 @@ impl Component {
             critical: critical,
         }
     }
+
+    /// Returns the temperature of the component (in celsius).
+    pub fn get_temperature(&self) -> f32 {
+        self.temperature
+    }
 }
 
 pub fn update_component(comp: &mut Component, temperature: f32) {
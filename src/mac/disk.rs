@@ -8,10 +8,11 @@ use ::DiskExt;
 use ::utils;
 
 use libc::statfs;
-use std::{mem, str};
+use std::{fs, mem, str};
 use std::fmt::{Debug, Error, Formatter};
 use std::path::{Path, PathBuf};
 use std::ffi::{OsStr, OsString};
+use std::os::unix::fs::MetadataExt;
 
 /// Enum containing the different handled disks types.
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -38,12 +39,16 @@ pub fn new(name: OsString, mount_point: &Path, type_: DiskType) -> Disk {
     let mount_point_cpath = utils::to_cpath(mount_point);
     let mut total_space = 0;
     let mut available_space = 0;
+    let mut total_inodes = 0;
+    let mut available_inodes = 0;
     let mut file_system = None;
     unsafe {
         let mut stat: statfs = mem::zeroed();
         if statfs(mount_point_cpath.as_ptr() as *const i8, &mut stat) == 0 {
             total_space = stat.f_bsize as u64 * stat.f_blocks as u64;
-            available_space = stat.f_bfree as u64  * stat.f_blocks as u64;
+            available_space = stat.f_bsize as u64 * stat.f_bavail as u64;
+            total_inodes = stat.f_files as u64;
+            available_inodes = stat.f_ffree as u64;
             let mut vec = Vec::with_capacity(stat.f_fstypename.len());
             for x in &stat.f_fstypename {
                 if *x == 0 {
@@ -54,6 +59,7 @@ pub fn new(name: OsString, mount_point: &Path, type_: DiskType) -> Disk {
             file_system = Some(vec);
         }
     }
+    let device_id = fs::metadata(mount_point).ok().map(|m| m.dev());
     Disk {
         type_: type_,
         name: name,
@@ -61,9 +67,17 @@ pub fn new(name: OsString, mount_point: &Path, type_: DiskType) -> Disk {
         mount_point: mount_point.to_owned(),
         total_space: total_space,
         available_space: available_space,
+        total_inodes: total_inodes,
+        available_inodes: available_inodes,
+        device_id: device_id,
     }
 }
 
+// Returns the device id of the mount point, read once when the disk was created.
+pub fn get_device_id(disk: &Disk) -> Option<u64> {
+    disk.device_id
+}
+
 /// Struct containing a disk information.
 pub struct Disk {
     type_: DiskType,
@@ -72,6 +86,9 @@ pub struct Disk {
     mount_point: PathBuf,
     total_space: u64,
     available_space: u64,
+    total_inodes: u64,
+    available_inodes: u64,
+    device_id: Option<u64>,
 }
 
 impl Debug for Disk {
@@ -108,12 +125,21 @@ impl DiskExt for Disk {
         self.available_space
     }
 
+    fn get_total_inodes(&self) -> u64 {
+        self.total_inodes
+    }
+
+    fn get_available_inodes(&self) -> u64 {
+        self.available_inodes
+    }
+
     fn update(&mut self) -> bool {
         unsafe {
             let mut stat: statfs = mem::zeroed();
             let mount_point_cpath = utils::to_cpath(&self.mount_point);
             if statfs(mount_point_cpath.as_ptr() as *const i8, &mut stat) == 0 {
                 self.available_space = stat.f_bsize as u64 * stat.f_bavail as u64;
+                self.available_inodes = stat.f_ffree as u64;
                 true
             } else {
                 false
@@ -10,7 +10,7 @@ use libc::{c_int, gid_t, kill, pid_t, uid_t};
 use ::ProcessExt;
 
 /// Enum describing the different status of a process.
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ProcessStatus {
     /// Process being created by fork.
     Idle,
@@ -123,6 +123,11 @@ pub struct Process {
     /// Pid of the parent process.
     pub parent: Option<pid_t>,
     /// Environment of the process.
+    ///
+    /// **WARNING**: This field is specific to macOS, use [`ProcessExt::get_environ`] to stay
+    /// portable: on Linux, the environment is only read on the first call to this method.
+    ///
+    /// [`ProcessExt::get_environ`]: trait.ProcessExt.html#tymethod.get_environ
     pub environ: Vec<String>,
     /// Current working directory.
     pub cwd: String,
@@ -181,6 +186,25 @@ impl ProcessExt for Process {
     fn kill(&self, signal: ::Signal) -> bool {
         unsafe { kill(self.pid, signal as c_int) == 0 }
     }
+
+    fn get_arguments(&self) -> &[String] {
+        let mut args = if self.cmd.is_empty() { &self.cmd[..] } else { &self.cmd[1..] };
+        // The command line ends with a null terminator which gives an empty last element.
+        if let Some((last, rest)) = args.split_last() {
+            if last.is_empty() {
+                args = rest;
+            }
+        }
+        args
+    }
+
+    fn get_memory_bytes(&self) -> u64 {
+        self.memory * 1024
+    }
+
+    fn get_environ(&self) -> &[String] {
+        &self.environ
+    }
 }
 
 #[allow(unused_must_use)]
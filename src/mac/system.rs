@@ -12,6 +12,7 @@ use sys::disk::{self, Disk, DiskType};
 use ::{DiskExt, ProcessExt, ProcessorExt, SystemExt};
 use std::collections::HashMap;
 use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
 use std::ffi::OsString;
 use libc::{self, c_void, c_int, pid_t, size_t, c_char, sysconf, _SC_PAGESIZE};
 use std::sync::Arc;
@@ -628,6 +629,9 @@ impl SystemExt for System {
     }
 
     fn get_process(&self, pid: pid_t) -> Option<&Process> {
+        if pid < 0 {
+            return None;
+        }
         self.process_list.get(&pid)
     }
 
@@ -641,6 +645,12 @@ impl SystemExt for System {
         ret
     }
 
+    fn get_processes_by_exe_path(&self, path: &Path) -> Vec<&Process> {
+        self.process_list.values()
+            .filter(|val| !val.exe.is_empty() && Path::new(&val.exe) == path)
+            .collect()
+    }
+
     fn get_processor_list(&self) -> &[Processor] {
         &self.processors[..]
     }
@@ -677,6 +687,30 @@ impl SystemExt for System {
     fn get_disks(&self) -> &[Disk] {
         &self.disks[..]
     }
+
+    fn get_name(&self) -> Option<String> {
+        Some("macOS".to_owned())
+    }
+
+    fn get_available_entropy(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_open_file_descriptors(&self) -> (u64, u64) {
+        unsafe fn get_int(name: &[u8]) -> u64 {
+            let mut value: c_int = 0;
+            let mut len = mem::size_of::<c_int>();
+            if libc::sysctlbyname(name.as_ptr() as *const c_char,
+                                  &mut value as *mut c_int as *mut c_void, &mut len,
+                                  ptr::null_mut(), 0) == 0 && value > 0 {
+                value as u64
+            } else {
+                0
+            }
+        }
+
+        unsafe { (get_int(b"kern.num_files\0"), get_int(b"kern.maxfiles\0")) }
+    }
 }
 
 impl Default for System {
@@ -0,0 +1,68 @@
+//
+// Sysinfo
+//
+// Copyright (c) 2017 Guillaume Gomez
+//
+
+use libc::pid_t;
+use sys::{Process, ProcessStatus};
+
+/// Owned summary of a process, independent from the `System` it comes from. Unlike `Process`,
+/// it doesn't contain the process' tasks so it's cheap to clone and to send to another thread.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessInfo {
+    /// Pid of the process.
+    pub pid: pid_t,
+    /// Pid of the parent process.
+    pub parent: Option<pid_t>,
+    /// Name of the program.
+    pub name: String,
+    /// Total cpu usage.
+    pub cpu_usage: f32,
+    /// Memory usage (in kB).
+    pub memory: u64,
+    /// Status of the process. `None` if it couldn't be retrieved.
+    pub status: Option<ProcessStatus>,
+}
+
+impl<'a> From<&'a Process> for ProcessInfo {
+    fn from(p: &'a Process) -> ProcessInfo {
+        ProcessInfo {
+            pid: p.pid,
+            parent: p.parent,
+            name: p.name.clone(),
+            cpu_usage: p.cpu_usage,
+            memory: p.memory,
+            #[cfg(not(target_os = "macos"))]
+            status: p.status,
+            #[cfg(target_os = "macos")]
+            status: p.process_status,
+        }
+    }
+}
+
+/// Owned copy of the system's information as it was when [`SystemExt::snapshot`] was called.
+/// Further refreshes of the `System` don't change it and it can be sent to another thread.
+///
+/// [`SystemExt::snapshot`]: trait.SystemExt.html#method.snapshot
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemSnapshot {
+    /// Total RAM size (in kB).
+    pub total_memory: u64,
+    /// Used RAM (in kB).
+    pub used_memory: u64,
+    /// Total SWAP size (in kB).
+    pub total_swap: u64,
+    /// Used SWAP (in kB).
+    pub used_swap: u64,
+    /// Usage of the processors, as fractions between `0` and `1` like
+    /// [`ProcessorExt::get_cpu_usage`]. The first one is the global usage like in
+    /// [`SystemExt::get_processor_list`].
+    ///
+    /// [`ProcessorExt::get_cpu_usage`]: trait.ProcessorExt.html#tymethod.get_cpu_usage
+    ///
+    /// [`SystemExt::get_processor_list`]: trait.SystemExt.html#tymethod.get_processor_list
+    pub processors_usage: Vec<f32>,
+    /// Processes, in no particular order.
+    pub processes: Vec<ProcessInfo>,
+}
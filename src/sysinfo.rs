@@ -70,6 +70,30 @@ pub use sys::{
     Disk,
     DiskType,
 };
+#[cfg(not(target_os = "macos"))]
+pub use sys::{
+    BlockDevice,
+    CacheInfo,
+    CounterMode,
+    DiskKind,
+    DiskListKind,
+    MemoryMapRegion,
+    MountInfo,
+    NetworkData,
+    NumaNode,
+    ProcReadError,
+    ProcessIo,
+    ProcessLimits,
+    ProcessNameSource,
+    ProcessStat,
+    ProtocolStats,
+    RefreshStats,
+    ResourceLimit,
+    SchedulingPolicy,
+    TcpConnection,
+    TcpState,
+    Virtualization,
+};
 pub use traits::{
     DiskExt,
     ProcessExt,
@@ -78,17 +102,24 @@ pub use traits::{
 };
 #[cfg(not(target_os = "windows"))]
 pub use utils::get_current_pid;
+pub use snapshot::{ProcessInfo, SystemSnapshot};
+pub use utils::{format_bytes, ByteSize};
 #[cfg(feature = "c-interface")]
 pub use c_interface::*;
+#[cfg(feature = "async")]
+pub use async_refresh::RefreshFuture;
 
 mod component;
 mod process;
 mod processor;
+mod snapshot;
 mod system;
 mod traits;
 mod utils;
 #[cfg(feature = "c-interface")]
 mod c_interface;
+#[cfg(feature = "async")]
+mod async_refresh;
 
 /// An enum representing signal on UNIX-like systems.
 #[repr(C)]
@@ -6,3 +6,17 @@
 
 // Once https://github.com/rust-lang/rfcs/blob/master/text/1422-pub-restricted.md
 // feature gets stabilized, we can move common parts in here.
+
+use std::fmt;
+use sys::System;
+use utils::ByteSize;
+use ::SystemExt;
+
+impl fmt::Display for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "memory: {} / {}", ByteSize(self.get_used_memory_bytes()),
+                 ByteSize(self.get_total_memory_bytes()))?;
+        write!(f, "swap  : {} / {}", ByteSize(self.get_used_swap_bytes()),
+               ByteSize(self.get_total_swap_bytes()))
+    }
+}
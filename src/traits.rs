@@ -5,10 +5,13 @@
 //
 
 use sys::{Component, Disk, DiskType, Process, Processor};
+use sys::disk::get_device_id;
+use snapshot::{ProcessInfo, SystemSnapshot};
 
-use libc::pid_t;
-use std::collections::HashMap;
+use libc::{kill, pid_t, EPERM};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
+use std::io;
 use std::path::Path;
 
 /// Contains all the methods of the `Disk` struct.
@@ -28,10 +31,18 @@ pub trait DiskExt {
     /// Returns the total disk size, in bytes.
     fn get_total_space(&self) -> u64;
 
-    /// Returns the available disk size, in bytes.
+    /// Returns the available disk size, in bytes. It's the space unprivileged users can use, so it
+    /// doesn't include the blocks reserved for the root user, like the "Avail" column of `df`.
     fn get_available_space(&self) -> u64;
 
-    /// Update the disk' information.
+    /// Returns the total number of inodes of the file system.
+    fn get_total_inodes(&self) -> u64;
+
+    /// Returns the number of free inodes of the file system.
+    fn get_available_inodes(&self) -> u64;
+
+    /// Update the disk' information. Returns `false` if the information couldn't be read, in
+    /// which case the previous values are kept.
     fn update(&mut self) -> bool;
 }
 
@@ -42,11 +53,24 @@ pub trait ProcessExt {
 
     /// Sends the given `signal` to the process.
     fn kill(&self, signal: ::Signal) -> bool;
+
+    /// Returns the command line arguments of the process, without the program (`argv[0]`).
+    fn get_arguments(&self) -> &[String];
+
+    /// Returns the memory usage of the process (in bytes). The `memory` field is in kB.
+    fn get_memory_bytes(&self) -> u64;
+
+    /// Returns the environment of the process.
+    ///
+    /// On macOS, it's read with the other information of the process and is also available in
+    /// the `environ` field. On Linux, there is no such field: it's only read on the first call,
+    /// and is empty if the process exited (or its pid was reused) before that.
+    fn get_environ(&self) -> &[String];
 }
 
 /// Contains all the methods of the `Processor` struct.
 pub trait ProcessorExt {
-    /// Returns this processor's usage.
+    /// Returns this processor's usage, as a fraction between `0` and `1`.
     fn get_cpu_usage(&self) -> f32;
 
     /// Returns this processor's name.
@@ -84,35 +108,253 @@ pub trait SystemExt {
     fn get_process_list(&self) -> &HashMap<pid_t, Process>;
 
     /// Returns the process corresponding to the given pid or `None` if no such process exists.
+    /// Negative pids (used for process groups by `kill`) always return `None`.
     fn get_process(&self, pid: pid_t) -> Option<&Process>;
 
+    /// Returns `true` if a process with the given pid currently exists, without refreshing
+    /// anything. It sends the null signal to the process, which only checks its existence, so
+    /// it's much cheaper than [`refresh_processes`] to poll a few processes. A process which exited
+    /// but wasn't reaped by its parent yet (a zombie) is still considered alive. Pids lower
+    /// than 1 always return `false`.
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    ///
+    /// let s = System::new();
+    /// println!("{}", s.is_process_alive(1337));
+    /// ```
+    ///
+    /// [`refresh_processes`]: trait.SystemExt.html#tymethod.refresh_processes
+    fn is_process_alive(&self, pid: pid_t) -> bool {
+        if pid < 1 {
+            return false;
+        }
+        if unsafe { kill(pid, 0) } == 0 {
+            return true;
+        }
+        // The process exists but we're not allowed to send it signals.
+        io::Error::last_os_error().raw_os_error() == Some(EPERM)
+    }
+
     /// Returns a list of process starting with the given name.
     fn get_process_by_name(&self, name: &str) -> Vec<&Process>;
 
+    /// Returns a list of process starting with the given name, ignoring the case ("chrome" also
+    /// matches "Chrome" for example). [`get_process_by_name`] is the case-sensitive version.
+    ///
+    /// [`get_process_by_name`]: trait.SystemExt.html#tymethod.get_process_by_name
+    fn get_processes_by_name_ignore_case(&self, name: &str) -> Vec<&Process> {
+        let name = name.to_lowercase();
+        self.get_process_list()
+            .values()
+            .filter(|p| p.name.to_lowercase().starts_with(&name))
+            .collect()
+    }
+
+    /// Returns a list of process whose executable path is equal to the given path.
+    fn get_processes_by_exe_path(&self, path: &Path) -> Vec<&Process>;
+
+    /// Returns the process corresponding to the given pid followed by its parent, the parent of
+    /// its parent and so on up to the first process (pid 1). The list stops early if an ancestor
+    /// isn't in the process list anymore. It's empty if there is no process with this pid.
+    fn get_process_ancestry(&self, pid: pid_t) -> Vec<&Process> {
+        let mut ret: Vec<&Process> = Vec::new();
+        let mut current = self.get_process(pid);
+
+        while let Some(p) = current {
+            // A reparented process could make a loop appear between two refreshes.
+            if ret.iter().any(|x| x.pid == p.pid) {
+                break;
+            }
+            ret.push(p);
+            current = match p.parent {
+                Some(parent) if p.pid != 1 => self.get_process(parent),
+                _ => None,
+            };
+        }
+        ret
+    }
+
+    /// Returns the direct children of the process corresponding to the given pid (the processes
+    /// whose parent is `pid`), in no particular order. Their own children aren't included.
+    fn get_children(&self, pid: pid_t) -> Vec<&Process> {
+        self.get_process_list().values().filter(|p| p.parent == Some(pid)).collect()
+    }
+
     /// The first processor in the array is the "main" process.
     fn get_processor_list(&self) -> &[Processor];
 
-    /// Returns total RAM size.
+    /// Returns total RAM size (in kB).
     fn get_total_memory(&self) -> u64;
 
-    /// Returns free RAM size.
+    /// Returns free RAM size (in kB).
     fn get_free_memory(&self) -> u64;
 
-    /// Returns used RAM size.
+    /// Returns used RAM size (in kB).
     fn get_used_memory(&self) -> u64;
 
-    /// Returns SWAP size.
+    /// Returns SWAP size (in kB).
     fn get_total_swap(&self) -> u64;
 
-    /// Returns free SWAP size.
+    /// Returns free SWAP size (in kB).
     fn get_free_swap(&self) -> u64;
 
-    /// Returns used SWAP size.
+    /// Returns used SWAP size (in kB).
     fn get_used_swap(&self) -> u64;
 
+    /// Returns total RAM size (in bytes).
+    fn get_total_memory_bytes(&self) -> u64 {
+        self.get_total_memory() * 1024
+    }
+
+    /// Returns free RAM size (in bytes).
+    fn get_free_memory_bytes(&self) -> u64 {
+        self.get_free_memory() * 1024
+    }
+
+    /// Returns used RAM size (in bytes).
+    fn get_used_memory_bytes(&self) -> u64 {
+        self.get_used_memory() * 1024
+    }
+
+    /// Returns SWAP size (in bytes).
+    fn get_total_swap_bytes(&self) -> u64 {
+        self.get_total_swap() * 1024
+    }
+
+    /// Returns free SWAP size (in bytes).
+    fn get_free_swap_bytes(&self) -> u64 {
+        self.get_free_swap() * 1024
+    }
+
+    /// Returns used SWAP size (in bytes).
+    fn get_used_swap_bytes(&self) -> u64 {
+        self.get_used_swap() * 1024
+    }
+
+    /// Returns `true` if the system has some SWAP.
+    fn is_swap_enabled(&self) -> bool {
+        self.get_total_swap() != 0
+    }
+
+    /// Returns the used SWAP as a percentage of the total one, or `0` if there is no SWAP.
+    fn get_used_swap_percentage(&self) -> f32 {
+        if self.is_swap_enabled() {
+            self.get_used_swap() as f32 * 100. / self.get_total_swap() as f32
+        } else {
+            0.
+        }
+    }
+
     /// Returns components list.
     fn get_components_list(&self) -> &[Component];
 
     /// Returns disks' list.
     fn get_disks(&self) -> &[Disk];
+
+    /// Returns the total size (in bytes) of the listed disks. A device mounted more than once
+    /// (bind mounts for example) is only counted once.
+    fn get_total_disk_space(&self) -> u64 {
+        unique_disks(self.get_disks()).iter().map(|d| d.get_total_space()).sum()
+    }
+
+    /// Returns the available size (in bytes) of the listed disks. A device mounted more than
+    /// once (bind mounts for example) is only counted once.
+    fn get_total_available_disk_space(&self) -> u64 {
+        unique_disks(self.get_disks()).iter().map(|d| d.get_available_space()).sum()
+    }
+
+    /// Returns the used size (in bytes) of the listed disks. A device mounted more than once
+    /// (bind mounts for example) is only counted once.
+    fn get_total_used_disk_space(&self) -> u64 {
+        self.get_total_disk_space().saturating_sub(self.get_total_available_disk_space())
+    }
+
+    /// Returns the disk containing `path`, which is the listed disk with the longest mount
+    /// point `path` starts with. `path` should be absolute; symbolic links aren't resolved.
+    fn get_disk_for_path(&self, path: &Path) -> Option<&Disk> {
+        self.get_disks()
+            .iter()
+            .filter(|d| path.starts_with(d.get_mount_point()))
+            .max_by_key(|d| d.get_mount_point().as_os_str().len())
+    }
+
+    /// Returns the disk containing the current working directory of the process corresponding
+    /// to the given pid (take a look at [`get_disk_for_path`]). Returns `None` if the process
+    /// doesn't exist or if its working directory couldn't be read.
+    ///
+    /// [`get_disk_for_path`]: #method.get_disk_for_path
+    fn get_disk_for_pid_cwd(&self, pid: pid_t) -> Option<&Disk> {
+        self.get_process(pid)
+            .filter(|p| !p.cwd.is_empty())
+            .and_then(|p| self.get_disk_for_path(Path::new(&p.cwd)))
+    }
+
+    /// Returns the name of the operating system (the distribution name on Linux, like "Ubuntu"
+    /// or "Fedora").
+    fn get_name(&self) -> Option<String>;
+
+    /// Returns the number of bits of entropy available in the kernel's random pool, or `None`
+    /// if this information isn't available on this platform. It is read on each call.
+    fn get_available_entropy(&self) -> Option<usize>;
+
+    /// Returns the number of file handles currently open on the whole system and the maximum
+    /// number the kernel allows (`fs.file-max` on Linux, `kern.maxfiles` on macOS). Once the
+    /// maximum is reached, no process can open files anymore. Both are `0` if the information
+    /// couldn't be read. It is read on each call.
+    fn get_open_file_descriptors(&self) -> (u64, u64);
+
+    /// Returns an owned summary of each process (pid, name, CPU and memory usage, status), in no
+    /// particular order. Unlike [`get_process_list`], the result doesn't borrow the `System` so
+    /// it can be kept across refreshes or sent to another thread.
+    ///
+    /// [`get_process_list`]: trait.SystemExt.html#tymethod.get_process_list
+    fn get_processes_owned(&self) -> Vec<ProcessInfo> {
+        self.get_process_list().values().map(ProcessInfo::from).collect()
+    }
+
+    /// Returns an owned copy of the memory, processors and processes information, which isn't
+    /// affected by the following refreshes and can be sent to another thread. It is much lighter
+    /// than the `System` itself since processes are summarized into [`ProcessInfo`].
+    ///
+    /// ```no_run
+    /// use sysinfo::{System, SystemExt};
+    /// use std::thread;
+    ///
+    /// let mut s = System::new();
+    /// s.refresh_all();
+    /// let snapshot = s.snapshot();
+    /// thread::spawn(move || {
+    ///     println!("{} processes", snapshot.processes.len());
+    /// });
+    /// ```
+    ///
+    /// [`ProcessInfo`]: struct.ProcessInfo.html
+    fn snapshot(&self) -> SystemSnapshot {
+        SystemSnapshot {
+            total_memory: self.get_total_memory(),
+            used_memory: self.get_used_memory(),
+            total_swap: self.get_total_swap(),
+            used_swap: self.get_used_swap(),
+            processors_usage: self.get_processor_list().iter().map(|p| p.get_cpu_usage()).collect(),
+            processes: self.get_processes_owned(),
+        }
+    }
+}
+
+// Returns the disks, skipping the ones whose file system has already been seen. Disks are
+// identified by the device id of their mount point (read when the disk was created, so this
+// doesn't block on a hung network mount): the name isn't enough since every overlay mount is
+// named "overlay" for example.
+fn unique_disks(disks: &[Disk]) -> Vec<&Disk> {
+    let mut seen = HashSet::new();
+    disks.iter()
+         .filter(|d| {
+             let key = match get_device_id(d) {
+                 Some(dev) => Ok(dev),
+                 None => Err(d.get_mount_point()),
+             };
+             seen.insert(key)
+         })
+         .collect()
 }
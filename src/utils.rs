@@ -4,6 +4,7 @@
 // Copyright (c) 2017 Guillaume Gomez
 //
 
+use std::fmt;
 #[cfg(not(target_os = "windows"))]
 use std::fs;
 #[cfg(not(target_os = "windows"))]
@@ -58,3 +59,37 @@ pub fn get_current_pid() -> pid_t {
 
     unsafe { getpid() }
 }
+
+/// A size in bytes, displayed using the closest binary unit ("1.50 GiB" for example).
+///
+/// ```
+/// use sysinfo::ByteSize;
+///
+/// assert_eq!(ByteSize(1536).to_string(), "1.50 KiB");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct ByteSize(pub u64);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.;
+        let mut unit = 0;
+        while value >= 1024. && unit + 1 < UNITS.len() {
+            value /= 1024.;
+            unit += 1;
+        }
+        write!(f, "{:.2} {}", value, UNITS[unit])
+    }
+}
+
+/// Formats `value` (in bytes) using the closest binary unit. See [`ByteSize`].
+///
+/// [`ByteSize`]: struct.ByteSize.html
+pub fn format_bytes(value: u64) -> String {
+    ByteSize(value).to_string()
+}